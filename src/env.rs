@@ -1,10 +1,11 @@
 use super::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path;
 use std::str;
 
 pub type TypeFn = fn(&mut Env, &[Node]) -> Node;
 pub type TypeCmd = fn(&mut Env, &[Token]) -> String;
+pub type TypeLazyFn = fn(&mut Env, &[Node]) -> Result<Node, MyError>;
 
 #[derive(Debug, Clone)]
 pub enum FloatFormat {
@@ -13,18 +14,72 @@ pub enum FloatFormat {
     Eng,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FloatPrecision {
+    Shortest,        // shortest round-trip form
+    Digits(usize),   // significant digits
+    Decimals(usize), // digits after the decimal point
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComplexFormat {
+    Rect,  // a+bi
+    Polar, // r∠θ
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AngleMode {
+    Rad,
+    Deg,
+    Grad,
+}
+
+impl AngleMode {
+    /// Radians per one unit of the angle mode.
+    pub fn radian(&self) -> f64 {
+        match self {
+            AngleMode::Rad => 1.0,
+            AngleMode::Deg => std::f64::consts::PI / 180.0,
+            AngleMode::Grad => std::f64::consts::PI / 200.0,
+        }
+    }
+}
+
+// Rounding of Q-format quantization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QRounding {
+    Nearest, // half away from zero
+    Even,    // half to even (convergent rounding)
+    Floor,   // toward -inf, i.e. plain arithmetic shift
+    Trunc,   // toward zero
+}
+
 // TODO: unit format changer
 #[derive(Clone)]
 pub struct Env<'a> {
     pub constant: HashMap<&'a str, Node>,
     pub variable: HashMap<String, Node>,
     pub func: HashMap<&'a str, (TypeFn, usize)>, // (function pointer, arg num: 0=variable)
-    pub user_func: HashMap<String, Vec<Token>>,  // user defined function
+    pub lazy_func: HashMap<&'a str, (TypeLazyFn, usize)>, // arguments are not evaluated, i.e. lambda
+    pub user_func: HashMap<String, Vec<Token>>,           // user defined function
     pub cmd: HashMap<&'a str, (TypeCmd, usize, &'a str)>, // (function pointer, arg num: 0=variable, description)
+    pub list_func: HashSet<&'a str>, // functions taking a list itself, others are applied to each element
     pub debug: bool,
-    pub output_radix: u8,
+    pub output_radix: u8,         // 0 = all radices at once
+    pub word_size: u32,           // bits for signed/unsigned interpretation, 0 = auto
+    pub leading_zero_octal: bool, // 011 is octal 9 (C style) or decimal 11
+    pub ieee_bits: u32,           // show IEEE-754 bit pattern of float, 32, 64 or 0 = off
+    pub q_rounding: QRounding,
+    pub q_saturate: bool, // saturate or wrap around on Q-format overflow
+    pub register: HashMap<String, Vec<(String, u32, u32)>>, // register layout: (field, msb, lsb)
     pub separate_digit: usize,
     pub float_format: FloatFormat,
+    pub float_precision: FloatPrecision,
+    pub complex_format: ComplexFormat,
+    pub imaginary_unit: char, // 'i' or 'j'
+    pub zero_threshold: f64,  // component of complex smaller than |c|*zero_threshold is shown as 0
+    pub angle_mode: AngleMode,
+    pub parse_units: bool, // parsing in `[...]`, a function name is a unit, i.e. `[deg]`
     pub history_path: path::PathBuf,
     pub history_max: usize,
     pub history_index: usize,
@@ -33,31 +88,33 @@ pub struct Env<'a> {
 
 // Implement of functions.
 
-fn impl_sin(_env: &mut Env, arg: &[Node]) -> Node {
+fn impl_sin(env: &mut Env, arg: &[Node]) -> Node {
+    let rad = env.angle_mode.radian();
     if let Node::Num(n, _) = &arg[0] {
         Node::FNum(
-            (*n as f64).sin(),
+            (*n as f64 * rad).sin(),
             Box::new(Node::Units(Box::new(Node::None))),
         ) // unit of sin() should be None
     } else if let Node::FNum(f, _) = &arg[0] {
-        Node::FNum(f.sin(), Box::new(Node::Units(Box::new(Node::None))))
+        Node::FNum((f * rad).sin(), Box::new(Node::Units(Box::new(Node::None))))
     } else if let Node::CNum(c, _) = &arg[0] {
-        Node::CNum(c.sin(), Box::new(Node::Units(Box::new(Node::None))))
+        Node::CNum((c * rad).sin(), Box::new(Node::Units(Box::new(Node::None))))
     } else {
         Node::None
     }
 }
 
-fn impl_cos(_env: &mut Env, arg: &[Node]) -> Node {
+fn impl_cos(env: &mut Env, arg: &[Node]) -> Node {
+    let rad = env.angle_mode.radian();
     if let Node::Num(n, _) = &arg[0] {
         Node::FNum(
-            (*n as f64).cos(),
+            (*n as f64 * rad).cos(),
             Box::new(Node::Units(Box::new(Node::None))),
         )
     } else if let Node::FNum(f, _) = &arg[0] {
-        Node::FNum(f.cos(), Box::new(Node::Units(Box::new(Node::None))))
+        Node::FNum((f * rad).cos(), Box::new(Node::Units(Box::new(Node::None))))
     } else if let Node::CNum(c, _) = &arg[0] {
-        Node::CNum(c.cos(), Box::new(Node::Units(Box::new(Node::None))))
+        Node::CNum((c * rad).cos(), Box::new(Node::Units(Box::new(Node::None))))
     } else {
         Node::None
     }
@@ -87,13 +144,16 @@ fn impl_abs(_env: &mut Env, arg: &[Node]) -> Node {
 }
 
 #[allow(clippy::if_same_then_else)]
-fn impl_arg(_env: &mut Env, arg: &[Node]) -> Node {
+fn impl_arg(env: &mut Env, arg: &[Node]) -> Node {
     if let Node::Num(_, _) = &arg[0] {
         Node::FNum(0.0, Box::new(Node::Units(Box::new(Node::None)))) // unit of arg() shall be None
     } else if let Node::FNum(_, _) = &arg[0] {
         Node::FNum(0.0, Box::new(Node::Units(Box::new(Node::None))))
     } else if let Node::CNum(c, _) = &arg[0] {
-        Node::FNum(c.arg(), Box::new(Node::Units(Box::new(Node::None))))
+        Node::FNum(
+            c.arg() / env.angle_mode.radian(),
+            Box::new(Node::Units(Box::new(Node::None))),
+        )
     } else {
         Node::None
     }
@@ -107,38 +167,305 @@ fn impl_sqrt(_env: &mut Env, arg: &[Node]) -> Node {
     )
 }
 
-fn impl_max(env: &mut Env, arg: &[Node]) -> Node {
-    if arg.is_empty() {
-        return Node::FNum(0.0, Box::new(Node::Units(Box::new(Node::None))));
+// Elements of list arguments are taken as arguments, i.e. max({1, 2}, 3) is max(1, 2, 3).
+fn flatten_list(arg: &[Node]) -> Vec<Node> {
+    let mut ret = Vec::new();
+    for a in arg {
+        match a {
+            Node::List(items) => ret.append(&mut flatten_list(items)),
+            _ => ret.push(a.clone()),
+        }
     }
-    let mut max = f64::MIN;
-    for i in arg {
-        if let Ok(val) = eval_fvalue(env, i) {
-            if max < val {
-                max = val;
+    ret
+}
+
+// Values of statistics functions with their units.
+// All arguments should be real and have the same units, otherwise `None` (error).
+fn stat_values(env: &mut Env, arg: &[Node]) -> Option<(Vec<f64>, Box<Node>)> {
+    let arg = &flatten_list(arg);
+    let units = match arg.first() {
+        Some(Node::Num(_, units)) | Some(Node::FNum(_, units)) => units.clone(),
+        _ => return None,
+    };
+    let mut values = Vec::new();
+    for a in arg {
+        match a {
+            Node::Num(_, u) | Node::FNum(_, u) => {
+                if !units_equal(env, u, &units) {
+                    return None;
+                }
             }
-        } else {
-            return Node::FNum(0.0, Box::new(Node::Units(Box::new(Node::None))));
-            // FIXME: use original units
+            _ => return None,
         }
+        values.push(eval_fvalue(env, a).ok()?);
+    }
+    Some((values, units))
+}
+
+fn stat_sub(env: &mut Env, arg: &[Node], f: fn(&[f64]) -> Option<f64>) -> Node {
+    match stat_values(env, arg) {
+        Some((values, units)) => match f(&values) {
+            Some(result) => Node::FNum(result, units),
+            None => Node::None,
+        },
+        None => Node::None,
     }
-    Node::FNum(max, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+// Result is dimensionless, i.e. units of variance are squared and not supported.
+fn stat_sub_no_units(env: &mut Env, arg: &[Node], f: fn(&[f64]) -> Option<f64>) -> Node {
+    match stat_sub(env, arg, f) {
+        Node::FNum(result, _) => Node::FNum(result, Box::new(Node::Units(Box::new(Node::None)))),
+        other => other,
+    }
+}
+
+fn stat_mean(x: &[f64]) -> Option<f64> {
+    Some(x.iter().sum::<f64>() / x.len() as f64)
+}
+
+// sum of squared deviation / (n - ddof)
+fn stat_variance(x: &[f64], ddof: usize) -> Option<f64> {
+    if x.len() <= ddof {
+        return None;
+    }
+    let mean = stat_mean(x)?;
+    Some(x.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (x.len() - ddof) as f64)
+}
+
+fn stat_sorted(x: &[f64]) -> Vec<f64> {
+    let mut sorted = x.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted
+}
+
+// linear interpolation between the closest ranks, p = 0..=100
+fn stat_percentile(x: &[f64], p: f64) -> Option<f64> {
+    if !(0.0..=100.0).contains(&p) {
+        return None;
+    }
+    let sorted = stat_sorted(x);
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+fn impl_max(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| x.iter().cloned().reduce(f64::max))
+}
+
+fn impl_min(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| x.iter().cloned().reduce(f64::min))
+}
+
+fn impl_sum(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| Some(x.iter().sum()))
+}
+
+// units of product are not supported
+fn impl_product(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub_no_units(env, arg, |x| Some(x.iter().product()))
 }
 
 fn impl_ave(env: &mut Env, arg: &[Node]) -> Node {
-    if arg.is_empty() {
-        return Node::FNum(0.0, Box::new(Node::Units(Box::new(Node::None))));
+    stat_sub(env, arg, stat_mean)
+}
+
+fn impl_median(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| stat_percentile(x, 50.0))
+}
+
+// the most frequent value, the smallest one if there are several
+fn impl_mode(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| {
+        let sorted = stat_sorted(x);
+        let mut best = (sorted[0], 0);
+        let mut index = 0;
+        while index < sorted.len() {
+            let count = sorted[index..]
+                .iter()
+                .take_while(|v| **v == sorted[index])
+                .count();
+            if count > best.1 {
+                best = (sorted[index], count);
+            }
+            index += count;
+        }
+        Some(best.0)
+    })
+}
+
+// sample variance (n - 1)
+fn impl_var(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub_no_units(env, arg, |x| stat_variance(x, 1))
+}
+
+// population variance (n)
+fn impl_varp(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub_no_units(env, arg, |x| stat_variance(x, 0))
+}
+
+// sample standard deviation (n - 1)
+fn impl_std(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| stat_variance(x, 1).map(f64::sqrt))
+}
+
+// population standard deviation (n)
+fn impl_stdp(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| stat_variance(x, 0).map(f64::sqrt))
+}
+
+fn impl_rms(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| {
+        Some((x.iter().map(|v| v * v).sum::<f64>() / x.len() as f64).sqrt())
+    })
+}
+
+// geometric mean of positive values
+fn impl_geomean(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| {
+        if x.iter().any(|v| *v <= 0.0) {
+            return None;
+        }
+        Some((x.iter().map(|v| v.ln()).sum::<f64>() / x.len() as f64).exp())
+    })
+}
+
+// harmonic mean of positive values
+fn impl_harmmean(env: &mut Env, arg: &[Node]) -> Node {
+    stat_sub(env, arg, |x| {
+        if x.iter().any(|v| *v <= 0.0) {
+            return None;
+        }
+        Some(x.len() as f64 / x.iter().map(|v| 1.0 / v).sum::<f64>())
+    })
+}
+
+// percentile(p, x1, x2, ...) : p = 0..=100
+fn impl_percentile(env: &mut Env, arg: &[Node]) -> Node {
+    if arg.len() < 2 {
+        return Node::None;
+    }
+    let p = match eval_fvalue(env, &arg[0]) {
+        Ok(p) => p,
+        Err(_) => return Node::None,
+    };
+    match stat_values(env, &arg[1..]) {
+        Some((values, units)) => match stat_percentile(&values, p) {
+            Some(result) => Node::FNum(result, units),
+            None => Node::None,
+        },
+        None => Node::None,
+    }
+}
+
+const SEQUENCE_MAX: usize = 1_000_000;
+
+// range(a, b, step), `b` is included. Integer arguments give integer values.
+fn impl_range(env: &mut Env, arg: &[Node]) -> Node {
+    if arg.len() != 2 && arg.len() != 3 {
+        return Node::None;
+    }
+    let (values, units) = match stat_values(env, arg) {
+        Some(v) => v,
+        None => return Node::None,
+    };
+    let (start, end) = (values[0], values[1]);
+    let step = if values.len() == 3 { values[2] } else { 1.0 };
+    if step == 0.0 || !step.is_finite() {
+        return Node::None;
+    }
+    let count = ((end - start) / step + 1e-9).floor() + 1.0;
+    if count > SEQUENCE_MAX as f64 {
+        return Node::None;
+    }
+    let integer = arg.iter().all(|a| matches!(a, Node::Num(_, _)));
+    let mut ret = Vec::new();
+    for i in 0..(count.max(0.0) as usize) {
+        let x = start + step * i as f64;
+        if integer {
+            ret.push(Node::Num(x.round() as i128, units.clone()));
+        } else {
+            ret.push(Node::FNum(x, units.clone()));
+        }
+    }
+    Node::List(ret)
+}
+
+fn linspace_values(env: &mut Env, arg: &[Node]) -> Option<(Vec<f64>, Box<Node>)> {
+    let (values, units) = stat_values(env, &arg[0..2])?;
+    let n = int_value(&arg[2])?;
+    if n < 1 || n > SEQUENCE_MAX as i128 {
+        return None;
+    }
+    let (start, end) = (values[0], values[1]);
+    let mut ret = Vec::new();
+    for i in 0..n {
+        if i == n - 1 && n > 1 {
+            ret.push(end);
+        } else if n > 1 {
+            ret.push(start + (end - start) * i as f64 / (n - 1) as f64);
+        } else {
+            ret.push(start);
+        }
+    }
+    Some((ret, units))
+}
+
+// n values from a to b with equal step
+fn impl_linspace(env: &mut Env, arg: &[Node]) -> Node {
+    match linspace_values(env, arg) {
+        Some((values, units)) => Node::List(
+            values
+                .into_iter()
+                .map(|x| Node::FNum(x, units.clone()))
+                .collect(),
+        ),
+        None => Node::None,
+    }
+}
+
+// n values from 10^a to 10^b with equal ratio, the values have the units of a and b,
+// i.e. logspace(0[Hz], 3[Hz], 4) for a frequency sweep
+fn impl_logspace(env: &mut Env, arg: &[Node]) -> Node {
+    match linspace_values(env, arg) {
+        Some((values, units)) => Node::List(
+            values
+                .into_iter()
+                .map(|x| Node::FNum(10f64.powf(x), units.clone()))
+                .collect(),
+        ),
+        None => Node::None,
+    }
+}
+
+// a value which is not a list is a sequence of one value
+fn lambda_sequence(env: &mut Env, arg: &Node) -> Result<Vec<Node>, MyError> {
+    match eval(env, arg)? {
+        Node::List(items) => Ok(items),
+        value => Ok(vec![value]),
+    }
+}
+
+fn impl_map(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    let mut ret = Vec::new();
+    for value in lambda_sequence(env, &arg[1])? {
+        ret.push(apply_lambda(env, &arg[0], value)?);
     }
-    let mut sum: f64 = 0.0;
-    for i in arg {
-        if let Ok(val) = eval_fvalue(env, i) {
-            sum += val;
+    Ok(Node::List(ret))
+}
+
+// keep values where `f` is not zero
+fn impl_filter(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    let mut ret = Vec::new();
+    for value in lambda_sequence(env, &arg[1])? {
+        let cond = apply_lambda(env, &arg[0], value.clone())?;
+        if eval_fvalue(env, &cond)? != 0.0 {
+            ret.push(value);
         }
     }
-    Node::FNum(
-        sum / arg.len() as f64,
-        Box::new(Node::Units(Box::new(Node::None))),
-    ) // FIXME: use original units
+    Ok(Node::List(ret))
 }
 
 fn impl_round_e12(input: f64) -> f64 {
@@ -192,30 +519,1043 @@ fn impl_e12(_env: &mut Env, arg: &[Node]) -> Node {
     }
 }
 
+// Real input inside of `domain` stays real,
+// otherwise (outside of the domain or complex input) it is calculated through `Complex64`.
+// Branch cuts of complex functions follow `num_complex`.
+fn impl_real_or_complex(
+    arg: &Node,
+    domain: fn(f64) -> bool,
+    f: fn(f64) -> f64,
+    cf: fn(Complex64) -> Complex64,
+) -> Node {
+    let value = match arg {
+        Node::Num(n, _) => Complex64::new(*n as f64, 0.0),
+        Node::FNum(f, _) => Complex64::new(*f, 0.0),
+        Node::CNum(c, _) => *c,
+        _ => return Node::None,
+    };
+    if value.im == 0.0 && domain(value.re) {
+        Node::FNum(f(value.re), Box::new(Node::Units(Box::new(Node::None)))) // unit of the result should be None
+    } else {
+        Node::CNum(cf(value), Box::new(Node::Units(Box::new(Node::None))))
+    }
+}
+
+fn domain_all(_x: f64) -> bool {
+    true
+}
+
+fn domain_non_negative(x: f64) -> bool {
+    x >= 0.0
+}
+
+fn domain_unit(x: f64) -> bool {
+    (-1.0..=1.0).contains(&x)
+}
+
+fn domain_ge_one(x: f64) -> bool {
+    x >= 1.0
+}
+
+// Convert an angle in the current angle mode into radian.
+fn angle_input(env: &Env, arg: &Node) -> Node {
+    let rad = env.angle_mode.radian();
+    match arg {
+        Node::Num(n, units) => Node::FNum(*n as f64 * rad, units.clone()),
+        Node::FNum(f, units) => Node::FNum(f * rad, units.clone()),
+        Node::CNum(c, units) => Node::CNum(c * rad, units.clone()),
+        _ => arg.clone(),
+    }
+}
+
+// Convert an angle in radian into the current angle mode.
+fn angle_output(env: &Env, result: Node) -> Node {
+    let rad = env.angle_mode.radian();
+    match result {
+        Node::FNum(f, units) => Node::FNum(f / rad, units),
+        Node::CNum(c, units) => Node::CNum(c / rad, units),
+        _ => result,
+    }
+}
+
+fn impl_tan(env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&angle_input(env, &arg[0]), domain_all, f64::tan, |c| {
+        c.tan()
+    })
+}
+
+fn impl_asin(env: &mut Env, arg: &[Node]) -> Node {
+    angle_output(
+        env,
+        impl_real_or_complex(&arg[0], domain_unit, f64::asin, |c| c.asin()),
+    )
+}
+
+fn impl_acos(env: &mut Env, arg: &[Node]) -> Node {
+    angle_output(
+        env,
+        impl_real_or_complex(&arg[0], domain_unit, f64::acos, |c| c.acos()),
+    )
+}
+
+fn impl_atan(env: &mut Env, arg: &[Node]) -> Node {
+    angle_output(
+        env,
+        impl_real_or_complex(&arg[0], domain_all, f64::atan, |c| c.atan()),
+    )
+}
+
+/// atan2(y, x): real input only.
+fn impl_atan2(env: &mut Env, arg: &[Node]) -> Node {
+    if let (Ok(y), Ok(x)) = (eval_fvalue(env, &arg[0]), eval_fvalue(env, &arg[1])) {
+        Node::FNum(
+            y.atan2(x) / env.angle_mode.radian(),
+            Box::new(Node::Units(Box::new(Node::None))),
+        )
+    } else {
+        Node::None
+    }
+}
+
+/// polar(magnitude, angle)
+fn impl_polar(_env: &mut Env, arg: &[Node]) -> Node {
+    Node::BinOp(
+        Token::Op(TokenOp::Angle),
+        Box::new(arg[0].clone()),
+        Box::new(arg[1].clone()),
+    )
+}
+
+/// deg(x): radian -> degree
+fn impl_deg(env: &mut Env, arg: &[Node]) -> Node {
+    if let Ok(f) = eval_fvalue(env, &arg[0]) {
+        Node::FNum(f.to_degrees(), Box::new(Node::Units(Box::new(Node::None))))
+    } else {
+        Node::None
+    }
+}
+
+/// rad(x): degree -> radian
+fn impl_rad(env: &mut Env, arg: &[Node]) -> Node {
+    if let Ok(f) = eval_fvalue(env, &arg[0]) {
+        Node::FNum(f.to_radians(), Box::new(Node::Units(Box::new(Node::None))))
+    } else {
+        Node::None
+    }
+}
+
+fn impl_sinh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_all, f64::sinh, |c| c.sinh())
+}
+
+fn impl_cosh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_all, f64::cosh, |c| c.cosh())
+}
+
+fn impl_tanh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_all, f64::tanh, |c| c.tanh())
+}
+
+fn impl_asinh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_all, f64::asinh, |c| c.asinh())
+}
+
+fn impl_acosh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_ge_one, f64::acosh, |c| c.acosh())
+}
+
+fn impl_atanh(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_unit, f64::atanh, |c| c.atanh())
+}
+
+fn impl_ln(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_non_negative, f64::ln, |c| c.ln())
+}
+
+fn impl_log10(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_non_negative, f64::log10, |c| c.log10())
+}
+
+fn impl_log2(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_real_or_complex(&arg[0], domain_non_negative, f64::log2, |c| c.log2())
+}
+
+/// log(x, base)
+fn impl_log(_env: &mut Env, arg: &[Node]) -> Node {
+    let base = impl_real_or_complex(&arg[1], domain_non_negative, f64::ln, |c| c.ln());
+    // base 0 and 1 are not defined
+    if let Node::FNum(f, _) = base {
+        if f == 0.0 || !f.is_finite() {
+            return Node::None;
+        }
+    }
+    Node::BinOp(
+        Token::Op(TokenOp::Div),
+        Box::new(impl_real_or_complex(
+            &arg[0],
+            domain_non_negative,
+            f64::ln,
+            |c| c.ln(),
+        )),
+        Box::new(base),
+    )
+}
+
+// Ratio in decibel/neper. Magnitude is used for complex input.
+// Negative power or magnitude, i.e. pow2db(-1), is out of the domain.
+fn impl_ratio_sub(arg: &Node, f: fn(f64) -> f64) -> Node {
+    let value = match arg {
+        Node::Num(n, _) => *n as f64,
+        Node::FNum(f, _) => *f,
+        Node::CNum(c, _) => c.norm(),
+        _ => return Node::None,
+    };
+    let ret = f(value);
+    if ret.is_nan() {
+        return Node::None;
+    }
+    Node::FNum(ret, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+const DBU_REFERENCE: f64 = 0.774_596_669_241_483_4; // sqrt(0.6) [V], 1mW into 600 ohm
+
+fn impl_pow2db(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0 * x.log10())
+}
+
+fn impl_db2pow(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0_f64.powf(x / 10.0))
+}
+
+fn impl_mag2db(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 20.0 * x.log10())
+}
+
+fn impl_db2mag(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0_f64.powf(x / 20.0))
+}
+
+fn impl_w2dbm(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0 * (x / 1e-3).log10())
+}
+
+fn impl_dbm2w(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 1e-3 * 10.0_f64.powf(x / 10.0))
+}
+
+fn impl_w2dbw(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0 * x.log10())
+}
+
+fn impl_dbw2w(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0_f64.powf(x / 10.0))
+}
+
+fn impl_v2dbv(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 20.0 * x.log10())
+}
+
+fn impl_dbv2v(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 10.0_f64.powf(x / 20.0))
+}
+
+fn impl_v2dbu(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| 20.0 * (x / DBU_REFERENCE).log10())
+}
+
+fn impl_dbu2v(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| DBU_REFERENCE * 10.0_f64.powf(x / 20.0))
+}
+
+fn impl_mag2np(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], f64::ln)
+}
+
+fn impl_np2mag(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], f64::exp)
+}
+
+// 1 Np = 20/ln(10) dB = 8.686 dB
+fn impl_np2db(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| x * 20.0 / std::f64::consts::LN_10)
+}
+
+fn impl_db2np(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_ratio_sub(&arg[0], |x| x * std::f64::consts::LN_10 / 20.0)
+}
+
+// IEEE-754 inspection. Bit patterns are `Num`, so they can be shown by `format 16`.
+fn ieee_value(arg: &Node) -> Option<f64> {
+    match arg {
+        Node::Num(n, _) => Some(*n as f64),
+        Node::FNum(f, _) => Some(*f),
+        _ => None,
+    }
+}
+
+fn ieee_bits_sub(arg: &Node, f: fn(f64) -> i128) -> Node {
+    match ieee_value(arg) {
+        Some(value) => Node::Num(f(value), Box::new(Node::Units(Box::new(Node::None)))),
+        None => Node::None,
+    }
+}
+
+fn impl_f64bits(_env: &mut Env, arg: &[Node]) -> Node {
+    ieee_bits_sub(&arg[0], |x| x.to_bits() as i128)
+}
+
+fn impl_f32bits(_env: &mut Env, arg: &[Node]) -> Node {
+    ieee_bits_sub(&arg[0], |x| (x as f32).to_bits() as i128)
+}
+
+// sign bit of f64
+fn impl_fsign(_env: &mut Env, arg: &[Node]) -> Node {
+    ieee_bits_sub(&arg[0], |x| (x.to_bits() >> 63) as i128)
+}
+
+// biased exponent field of f64 (11 bits)
+fn impl_fexp(_env: &mut Env, arg: &[Node]) -> Node {
+    ieee_bits_sub(&arg[0], |x| ((x.to_bits() >> 52) & 0x7ff) as i128)
+}
+
+// mantissa (fraction) field of f64 (52 bits)
+fn impl_fmant(_env: &mut Env, arg: &[Node]) -> Node {
+    ieee_bits_sub(&arg[0], |x| (x.to_bits() & 0xf_ffff_ffff_ffff) as i128)
+}
+
+fn impl_bits2f64(_env: &mut Env, arg: &[Node]) -> Node {
+    if let Node::Num(n, _) = &arg[0] {
+        Node::FNum(
+            f64::from_bits(*n as u64),
+            Box::new(Node::Units(Box::new(Node::None))),
+        )
+    } else {
+        Node::None
+    }
+}
+
+fn impl_bits2f32(_env: &mut Env, arg: &[Node]) -> Node {
+    if let Node::Num(n, _) = &arg[0] {
+        Node::FNum(
+            f32::from_bits(*n as u32) as f64,
+            Box::new(Node::Units(Box::new(Node::None))),
+        )
+    } else {
+        Node::None
+    }
+}
+
+// Q-format (fixed point) conversion.
+// Qm.n has m integer bits including the sign bit and n fractional bits, i.e. Q1.15 is 16 bits.
+fn q_format(m: &Node, n: &Node) -> Option<(u32, u32)> {
+    if let (Node::Num(m, _), Node::Num(n, _)) = (m, n) {
+        if *m >= 1 && *n >= 0 && m + n <= 64 {
+            return Some((*m as u32, *n as u32));
+        }
+    }
+    None
+}
+
+// Sign extend lower `bits` of `k`.
+fn q_sign_extend(k: i128, bits: u32) -> i128 {
+    (k << (128 - bits)) >> (128 - bits)
+}
+
+fn q_quantize(env: &Env, x: f64, m: u32, n: u32) -> i128 {
+    let scaled = x * 2f64.powi(n as i32);
+    let rounded = match env.q_rounding {
+        QRounding::Nearest => scaled.round(),
+        QRounding::Even => {
+            if (scaled - scaled.trunc()).abs() == 0.5 {
+                2.0 * (scaled / 2.0).round()
+            } else {
+                scaled.round()
+            }
+        }
+        QRounding::Floor => scaled.floor(),
+        QRounding::Trunc => scaled.trunc(),
+    };
+    let bits = m + n;
+    if env.q_saturate {
+        let max = (1i128 << (bits - 1)) - 1;
+        let min = -(1i128 << (bits - 1));
+        (rounded as i128).max(min).min(max)
+    } else {
+        q_sign_extend(rounded as i128, bits)
+    }
+}
+
+fn q_dequantize(k: i128, m: u32, n: u32) -> f64 {
+    q_sign_extend(k, m + n) as f64 / 2f64.powi(n as i32)
+}
+
+// q(x, m, n) : quantize x to Qm.n integer
+fn impl_q(env: &mut Env, arg: &[Node]) -> Node {
+    match (ieee_value(&arg[0]), q_format(&arg[1], &arg[2])) {
+        (Some(x), Some((m, n))) => Node::Num(
+            q_quantize(env, x, m, n),
+            Box::new(Node::Units(Box::new(Node::None))),
+        ),
+        _ => Node::None,
+    }
+}
+
+// fromq(k, m, n) : Qm.n integer to real. k is two's complement of m+n bits, i.e. 0xc000 of Q1.15 is -0.5.
+fn impl_fromq(_env: &mut Env, arg: &[Node]) -> Node {
+    match (&arg[0], q_format(&arg[1], &arg[2])) {
+        (Node::Num(k, _), Some((m, n))) => Node::FNum(
+            q_dequantize(*k, m, n),
+            Box::new(Node::Units(Box::new(Node::None))),
+        ),
+        _ => Node::None,
+    }
+}
+
+// qerr(x, m, n) : quantization error, fromq(q(x, m, n), m, n) - x
+fn impl_qerr(env: &mut Env, arg: &[Node]) -> Node {
+    match (ieee_value(&arg[0]), q_format(&arg[1], &arg[2])) {
+        (Some(x), Some((m, n))) => Node::FNum(
+            q_dequantize(q_quantize(env, x, m, n), m, n) - x,
+            Box::new(Node::Units(Box::new(Node::None))),
+        ),
+        _ => Node::None,
+    }
+}
+
+// Bit operations on Num.
+// Width of popcount/clz/ctz/bswap/bitrev is the optional 2nd argument, `format word` or 32 bits.
+fn bit_width(env: &Env, arg: &[Node]) -> Option<u32> {
+    match arg.get(1) {
+        Some(Node::Num(w, _)) if (1..=128).contains(w) => Some(*w as u32),
+        Some(_) => None,
+        None if env.word_size != 0 => Some(env.word_size),
+        None => Some(32),
+    }
+}
+
+fn bit_mask(width: u32) -> u128 {
+    if width >= 128 {
+        u128::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn bit_position(arg: &Node) -> Option<u32> {
+    match arg {
+        Node::Num(n, _) if (0..128).contains(n) => Some(*n as u32),
+        _ => None,
+    }
+}
+
+// (value masked to width, width)
+fn bit_unary_sub(env: &Env, arg: &[Node], f: fn(u128, u32) -> u128) -> Node {
+    if arg.is_empty() || arg.len() > 2 {
+        return Node::None;
+    }
+    match (&arg[0], bit_width(env, arg)) {
+        (Node::Num(n, _), Some(width)) => Node::Num(
+            f(*n as u128 & bit_mask(width), width) as i128,
+            Box::new(Node::Units(Box::new(Node::None))),
+        ),
+        _ => Node::None,
+    }
+}
+
+// bits(x, msb, lsb) : extract bit field
+fn impl_bits(_env: &mut Env, arg: &[Node]) -> Node {
+    match (&arg[0], bit_position(&arg[1]), bit_position(&arg[2])) {
+        (Node::Num(n, _), Some(msb), Some(lsb)) if msb >= lsb => Node::Num(
+            ((*n as u128 >> lsb) & bit_mask(msb - lsb + 1)) as i128,
+            Box::new(Node::Units(Box::new(Node::None))),
+        ),
+        _ => Node::None,
+    }
+}
+
+// insbits(x, msb, lsb, v) : replace bit field with v
+fn impl_insbits(_env: &mut Env, arg: &[Node]) -> Node {
+    match (
+        &arg[0],
+        bit_position(&arg[1]),
+        bit_position(&arg[2]),
+        &arg[3],
+    ) {
+        (Node::Num(n, _), Some(msb), Some(lsb), Node::Num(v, _)) if msb >= lsb => {
+            let mask = bit_mask(msb - lsb + 1) << lsb;
+            Node::Num(
+                ((*n as u128 & !mask) | ((*v as u128) << lsb & mask)) as i128,
+                Box::new(Node::Units(Box::new(Node::None))),
+            )
+        }
+        _ => Node::None,
+    }
+}
+
+fn bit_single_sub(arg: &[Node], f: fn(i128, u32) -> i128) -> Node {
+    match (&arg[0], bit_position(&arg[1])) {
+        (Node::Num(n, _), Some(bit)) => {
+            Node::Num(f(*n, bit), Box::new(Node::Units(Box::new(Node::None))))
+        }
+        _ => Node::None,
+    }
+}
+
+fn impl_bset(_env: &mut Env, arg: &[Node]) -> Node {
+    bit_single_sub(arg, |n, bit| n | 1 << bit)
+}
+
+fn impl_bclr(_env: &mut Env, arg: &[Node]) -> Node {
+    bit_single_sub(arg, |n, bit| n & !(1 << bit))
+}
+
+fn impl_btest(_env: &mut Env, arg: &[Node]) -> Node {
+    bit_single_sub(arg, |n, bit| n >> bit & 1)
+}
+
+fn impl_popcount(env: &mut Env, arg: &[Node]) -> Node {
+    bit_unary_sub(env, arg, |n, _| n.count_ones() as u128)
+}
+
+fn impl_clz(env: &mut Env, arg: &[Node]) -> Node {
+    bit_unary_sub(env, arg, |n, width| {
+        (n.leading_zeros() - (128 - width)) as u128
+    })
+}
+
+fn impl_ctz(env: &mut Env, arg: &[Node]) -> Node {
+    bit_unary_sub(env, arg, |n, width| n.trailing_zeros().min(width) as u128)
+}
+
+fn impl_bswap(env: &mut Env, arg: &[Node]) -> Node {
+    if let Some(width) = bit_width(env, arg) {
+        if width % 8 != 0 {
+            return Node::None;
+        }
+    }
+    bit_unary_sub(env, arg, |n, width| n.swap_bytes() >> (128 - width))
+}
+
+fn impl_bitrev(env: &mut Env, arg: &[Node]) -> Node {
+    bit_unary_sub(env, arg, |n, width| n.reverse_bits() >> (128 - width))
+}
+
+// Rounding keeps units. The result is Num if it is representable, so it can be used by bit operations.
+fn float_to_int(f: f64, units: Box<Node>) -> Node {
+    if f.is_finite() && f.abs() < 1e38 {
+        Node::Num(f as i128, units)
+    } else {
+        Node::FNum(f, units)
+    }
+}
+
+fn impl_rounding_sub(arg: &Node, f: fn(f64) -> f64) -> Node {
+    match arg {
+        Node::Num(n, units) => Node::Num(*n, units.clone()),
+        Node::FNum(x, units) => float_to_int(f(*x), units.clone()),
+        Node::CNum(c, units) => Node::CNum(Complex64::new(f(c.re), f(c.im)), units.clone()),
+        _ => Node::None,
+    }
+}
+
+// Integer `n` rounded to a multiple of `m`, half away from zero.
+fn round_int_multiple(n: i128, m: i128) -> Option<i128> {
+    let m = m.checked_abs()?;
+    let q = n / m;
+    let r = n % m;
+    let q = if r.abs() >= m - r.abs() {
+        q + r.signum()
+    } else {
+        q
+    };
+    q.checked_mul(m)
+}
+
+fn impl_floor(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_rounding_sub(&arg[0], f64::floor)
+}
+
+fn impl_ceil(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_rounding_sub(&arg[0], f64::ceil)
+}
+
+fn impl_trunc(_env: &mut Env, arg: &[Node]) -> Node {
+    impl_rounding_sub(&arg[0], f64::trunc)
+}
+
+// round(x) : nearest integer, round(x, n) : n digits after the decimal point (n < 0 rounds to 10^-n)
+fn impl_round(_env: &mut Env, arg: &[Node]) -> Node {
+    match arg {
+        [x] => impl_rounding_sub(x, f64::round),
+        [x, Node::Num(digits, _)] => round_decimals(x, *digits),
+        _ => Node::None,
+    }
+}
+
+fn round_decimals(x: &Node, digits: i128) -> Node {
+    if digits.abs() > 300 {
+        return Node::None;
+    }
+    let scale = 10f64.powi(digits as i32);
+    match x {
+        Node::Num(n, units) if digits < 0 => match 10i128.checked_pow(-digits as u32) {
+            Some(m) => match round_int_multiple(*n, m) {
+                Some(rounded) => Node::Num(rounded, units.clone()),
+                None => Node::None,
+            },
+            None => Node::Num(0, units.clone()),
+        },
+        Node::Num(n, units) => Node::Num(*n, units.clone()),
+        Node::FNum(f, units) if digits <= 0 => {
+            float_to_int((f * scale).round() / scale, units.clone())
+        }
+        Node::FNum(f, units) => Node::FNum((f * scale).round() / scale, units.clone()),
+        Node::CNum(c, units) => Node::CNum(
+            Complex64::new(
+                (c.re * scale).round() / scale,
+                (c.im * scale).round() / scale,
+            ),
+            units.clone(),
+        ),
+        _ => Node::None,
+    }
+}
+
+// roundsig(x, n) : n significant digits
+fn impl_roundsig(_env: &mut Env, arg: &[Node]) -> Node {
+    match (ieee_value(&arg[0]), &arg[1]) {
+        (Some(x), Node::Num(n, _)) if *n > 0 => {
+            if x == 0.0 || !x.is_finite() {
+                return arg[0].clone();
+            }
+            let exponent = x.abs().log10().floor() as i128;
+            match (*n - 1).checked_sub(exponent) {
+                Some(digits) => round_decimals(&arg[0], digits),
+                None => Node::None,
+            }
+        }
+        _ => Node::None,
+    }
+}
+
+// mround(x, m) : nearest multiple of m
+fn impl_mround(_env: &mut Env, arg: &[Node]) -> Node {
+    match (&arg[0], &arg[1]) {
+        (_, Node::Num(0, _)) => Node::None,
+        (Node::Num(n, units), Node::Num(m, _)) => match round_int_multiple(*n, *m) {
+            Some(rounded) => Node::Num(rounded, units.clone()),
+            None => Node::None,
+        },
+        (Node::Num(_, units), _) | (Node::FNum(_, units), _) => {
+            match (ieee_value(&arg[0]), ieee_value(&arg[1])) {
+                (Some(x), Some(m)) if m != 0.0 => Node::FNum((x / m).round() * m, units.clone()),
+                _ => Node::None,
+            }
+        }
+        _ => Node::None,
+    }
+}
+
+// sign(x) : -1, 0 or 1, c/|c| for complex
+fn impl_sign(_env: &mut Env, arg: &[Node]) -> Node {
+    let units = Box::new(Node::Units(Box::new(Node::None)));
+    match &arg[0] {
+        Node::Num(n, _) => Node::Num(n.signum(), units),
+        Node::FNum(f, _) if f.is_nan() => Node::None,
+        Node::FNum(f, _) if *f == 0.0 => Node::Num(0, units),
+        Node::FNum(f, _) => Node::Num(f.signum() as i128, units),
+        Node::CNum(c, _) if c.norm() == 0.0 => Node::Num(0, units),
+        Node::CNum(c, _) => Node::CNum(c / c.norm(), units),
+        _ => Node::None,
+    }
+}
+
+// frac(x) : x - trunc(x)
+fn impl_frac(_env: &mut Env, arg: &[Node]) -> Node {
+    match &arg[0] {
+        Node::Num(_, units) => Node::Num(0, units.clone()),
+        Node::FNum(f, units) => Node::FNum(f.fract(), units.clone()),
+        Node::CNum(c, units) => {
+            Node::CNum(Complex64::new(c.re.fract(), c.im.fract()), units.clone())
+        }
+        _ => Node::None,
+    }
+}
+
+// int(x) : FNum to Num toward zero, error if it does not fit
+fn impl_int(_env: &mut Env, arg: &[Node]) -> Node {
+    match &arg[0] {
+        Node::Num(n, units) => Node::Num(*n, units.clone()),
+        Node::FNum(f, units) if f.is_finite() && f.abs() < 1.7e38 => {
+            Node::Num(*f as i128, units.clone())
+        }
+        _ => Node::None,
+    }
+}
+
+// Number theory on Num. Integral FNum such as `1e6` is accepted too.
+fn int_value(arg: &Node) -> Option<i128> {
+    match arg {
+        Node::Num(n, _) => Some(*n),
+        Node::FNum(f, _) if f.fract() == 0.0 && f.abs() < 1e38 => Some(*f as i128),
+        _ => None,
+    }
+}
+
+fn int_node(n: i128) -> Node {
+    Node::Num(n, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+// gcd of absolute values, unsigned as |i128::MIN| does not fit in i128
+fn gcd(a: u128, b: u128) -> u128 {
+    let (mut a, mut b) = (a, b);
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+// a * b % m without overflow, 0 <= a, b < m
+fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    if m <= u64::MAX as u128 {
+        return a * b % m;
+    }
+    let (mut a, mut b, mut result) = (a, b, 0u128);
+    while b > 0 {
+        if b & 1 == 1 {
+            result = if result >= m - a {
+                result - (m - a)
+            } else {
+                result + a
+            };
+        }
+        a = if a >= m - a { a - (m - a) } else { a + a };
+        b >>= 1;
+    }
+    result
+}
+
+fn pow_mod(base: u128, mut exponent: u128, m: u128) -> u128 {
+    let mut base = base % m;
+    let mut result = 1 % m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+// Miller-Rabin. These bases are deterministic below 3.3e24,
+// a larger n which passes is a probable prime.
+fn is_prime(n: u128) -> bool {
+    const BASES: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+    if n < 2 {
+        return false;
+    }
+    for p in BASES.iter() {
+        if n.is_multiple_of(*p) {
+            return n == *p;
+        }
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for a in BASES.iter() {
+        let mut x = pow_mod(*a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// Steps of Pollard's rho, enough for factors up to about 2^36
+const POLLARD_RHO_MAX: usize = 1 << 18;
+
+// Pollard's rho with Floyd's cycle detection. `n` is composite and odd.
+// None if no factor is found in `steps`.
+fn pollard_rho(n: u128, steps: &mut usize) -> Option<u128> {
+    let mut c = 1;
+    loop {
+        let f = |x: u128| (mul_mod(x, x, n) + c) % n;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            if *steps >= POLLARD_RHO_MAX {
+                return None;
+            }
+            *steps += 1;
+            x = f(x);
+            y = f(f(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        if d != n {
+            return Some(d);
+        }
+        c += 1;
+    }
+}
+
+// false if a factor is too large to find
+fn factorize(n: u128, factors: &mut Vec<u128>, steps: &mut usize) -> bool {
+    if n == 1 {
+        return true;
+    }
+    if is_prime(n) {
+        factors.push(n);
+        return true;
+    }
+    for p in [2, 3, 5, 7, 11, 13].iter() {
+        if n.is_multiple_of(*p) {
+            factors.push(*p);
+            return factorize(n / p, factors, steps);
+        }
+    }
+    match pollard_rho(n, steps) {
+        Some(d) => factorize(d, factors, steps) && factorize(n / d, factors, steps),
+        None => false,
+    }
+}
+
+// gcd(a, b, ...)
+fn impl_gcd(_env: &mut Env, arg: &[Node]) -> Node {
+    let arg = &flatten_list(arg);
+    let mut result = 0;
+    for a in arg {
+        match int_value(a) {
+            Some(a) => result = gcd(result, a.unsigned_abs()),
+            None => return Node::None,
+        }
+    }
+    if arg.is_empty() {
+        return Node::None;
+    }
+    if result > i128::MAX as u128 {
+        return Node::None;
+    }
+    int_node(result as i128)
+}
+
+// lcm(a, b, ...)
+fn impl_lcm(_env: &mut Env, arg: &[Node]) -> Node {
+    let arg = &flatten_list(arg);
+    let mut result: u128 = 1;
+    for a in arg {
+        match int_value(a) {
+            Some(0) => result = 0,
+            Some(a) if result != 0 => {
+                let a = a.unsigned_abs();
+                match (result / gcd(result, a)).checked_mul(a) {
+                    Some(lcm) => result = lcm,
+                    None => return Node::None,
+                }
+            }
+            Some(_) => {}
+            None => return Node::None,
+        }
+    }
+    if arg.is_empty() {
+        return Node::None;
+    }
+    if result > i128::MAX as u128 {
+        return Node::None;
+    }
+    int_node(result as i128)
+}
+
+// modpow(base, exponent, modulus) : base^exponent mod modulus
+fn impl_modpow(_env: &mut Env, arg: &[Node]) -> Node {
+    match (int_value(&arg[0]), int_value(&arg[1]), int_value(&arg[2])) {
+        (Some(base), Some(exponent), Some(m)) if exponent >= 0 && m > 0 => {
+            let base = base.rem_euclid(m) as u128;
+            int_node(pow_mod(base, exponent as u128, m as u128) as i128)
+        }
+        _ => Node::None,
+    }
+}
+
+// modinv(a, modulus) : x of a*x = 1 mod modulus, error if gcd(a, modulus) != 1
+fn impl_modinv(_env: &mut Env, arg: &[Node]) -> Node {
+    match (int_value(&arg[0]), int_value(&arg[1])) {
+        (Some(a), Some(m)) if m > 0 => {
+            // extended Euclid
+            let (mut r0, mut r1) = (a.rem_euclid(m), m);
+            let (mut x0, mut x1) = (1i128, 0i128);
+            while r1 != 0 {
+                let q = r0 / r1;
+                let (r, x) = (r0 - q * r1, x0 - q * x1);
+                r0 = r1;
+                r1 = r;
+                x0 = x1;
+                x1 = x;
+            }
+            if r0 != 1 && m != 1 {
+                return Node::None;
+            }
+            int_node(x0.rem_euclid(m))
+        }
+        _ => Node::None,
+    }
+}
+
+// isprime(n) : 1 or 0
+fn impl_isprime(_env: &mut Env, arg: &[Node]) -> Node {
+    match int_value(&arg[0]) {
+        Some(n) => int_node((n > 0 && is_prime(n as u128)) as i128),
+        None => Node::None,
+    }
+}
+
+// nextprime(n) : the smallest prime greater than n
+fn impl_nextprime(_env: &mut Env, arg: &[Node]) -> Node {
+    match int_value(&arg[0]) {
+        Some(n) => {
+            let mut p = if n < 2 { 2 } else { n as u128 + 1 };
+            while !is_prime(p) {
+                p += 1;
+            }
+            int_node(p as i128)
+        }
+        None => Node::None,
+    }
+}
+
+// fact(n) : n!, float if it overflows, inf above 170!
+fn impl_fact(_env: &mut Env, arg: &[Node]) -> Node {
+    match int_value(&arg[0]) {
+        Some(n) if n > 170 => {
+            Node::FNum(f64::INFINITY, Box::new(Node::Units(Box::new(Node::None))))
+        }
+        Some(n) if n >= 0 => {
+            let mut result: i128 = 1;
+            for i in 2..=n {
+                match result.checked_mul(i) {
+                    Some(r) => result = r,
+                    None => {
+                        let f = (2..=n).fold(1.0, |acc, i| acc * i as f64);
+                        return Node::FNum(f, Box::new(Node::Units(Box::new(Node::None))));
+                    }
+                }
+            }
+            int_node(result)
+        }
+        _ => Node::None,
+    }
+}
+
+// binom(n, k) : n choose k, float if it overflows
+fn impl_binom(_env: &mut Env, arg: &[Node]) -> Node {
+    match (int_value(&arg[0]), int_value(&arg[1])) {
+        (Some(n), Some(k)) if n >= 0 && k >= 0 => {
+            if k > n {
+                return int_node(0);
+            }
+            let k = k.min(n - k);
+            let mut result: i128 = 1;
+            for i in 1..=k {
+                // result * (n - k + i) / i is C(n - k + i, i), (n - k + i) is divisible by i / g
+                let g = gcd(result as u128, i as u128) as i128;
+                match (result / g).checked_mul((n - k + i) / (i / g)) {
+                    Some(r) => result = r,
+                    None => {
+                        // each factor is 2 or more, the product reaches inf in about 1000 steps
+                        let mut f = 1.0;
+                        for i in 1..=k {
+                            f *= (n - k + i) as f64 / i as f64;
+                            if f.is_infinite() {
+                                break;
+                            }
+                        }
+                        return Node::FNum(f.round(), Box::new(Node::Units(Box::new(Node::None))));
+                    }
+                }
+            }
+            int_node(result)
+        }
+        _ => Node::None,
+    }
+}
+
+// isqrt(n) : floor(sqrt(n))
+fn impl_isqrt(_env: &mut Env, arg: &[Node]) -> Node {
+    match int_value(&arg[0]) {
+        Some(n) if n >= 0 => {
+            let n = n as u128;
+            let mut x = (n as f64).sqrt() as u128;
+            while x * x > n {
+                x -= 1;
+            }
+            while (x + 1) * (x + 1) <= n {
+                x += 1;
+            }
+            int_node(x as i128)
+        }
+        _ => Node::None,
+    }
+}
+
 // Implement of commands.
 // TODO: add unit format
 fn impl_output_format(env: &mut Env, arg: &[Token]) -> String {
     if env.is_debug() {
         eprintln!("impl_output_format {:?}\r", arg);
     }
-    for a in arg {
-        match a {
-            Token::Num(2) => {
-                env.output_radix = 2;
-            }
-            Token::Num(10) => {
-                env.output_radix = 10;
-            }
-            Token::Num(16) => {
-                env.output_radix = 16;
+    let mut index = 0;
+    while index < arg.len() {
+        match &arg[index] {
+            Token::Num(n) if (2..=36).contains(n) => {
+                env.output_radix = *n as u8;
             }
             Token::Ident(id) => {
                 if id == "radix2" || id == "binary" {
                     env.output_radix = 2;
+                } else if id == "radix8" || id == "octal" {
+                    env.output_radix = 8;
                 } else if id == "radix10" || id == "decimal" {
                     env.output_radix = 10;
                 } else if id == "radix16" || id == "hexadecimal" {
-                    env.output_radix = 2;
+                    env.output_radix = 16;
+                } else if id == "radix" {
+                    // format radix 36 : 36#zz
+                    if let Some(Token::Num(n)) = arg.get(index + 1) {
+                        if (2..=36).contains(n) {
+                            env.output_radix = *n as u8;
+                        }
+                        index += 1;
+                    }
+                } else if id == "ieee" {
+                    // format ieee 32 : show f32 bit pattern, format ieee 0 : off
+                    match arg.get(index + 1) {
+                        Some(Token::Num(n)) => {
+                            if [0, 32, 64].contains(n) {
+                                env.ieee_bits = *n as u32;
+                            }
+                            index += 1;
+                        }
+                        _ => {
+                            env.ieee_bits = 64;
+                        }
+                    }
+                } else if id == "all" {
+                    env.output_radix = 0;
+                } else if id == "word" {
+                    // format word 16 : interpret as 16 bit signed/unsigned, 0 to size automatically.
+                    if let Some(Token::Num(n)) = arg.get(index + 1) {
+                        if [0, 8, 16, 32, 64, 128].contains(n) {
+                            env.word_size = *n as u32;
+                        }
+                        index += 1;
+                    }
                 } else if id == "sep3" {
                     env.separate_digit = 3;
                 } else if id == "sep4" {
@@ -228,14 +1568,57 @@ fn impl_output_format(env: &mut Env, arg: &[Token]) -> String {
                     env.float_format = FloatFormat::Eng;
                 } else if id == "fix" {
                     env.float_format = FloatFormat::Fix;
+                } else if id == "shortest" {
+                    env.float_precision = FloatPrecision::Shortest;
+                } else if id == "digits" || id == "decimals" {
+                    // format digits 4 : 4 significant digits
+                    // format decimals 3 : 3 digits after the decimal point
+                    if let Some(Token::Num(n)) = arg.get(index + 1) {
+                        if id == "digits" && *n > 0 {
+                            env.float_precision = FloatPrecision::Digits(*n as usize);
+                        } else if id == "decimals" && *n >= 0 {
+                            env.float_precision = FloatPrecision::Decimals(*n as usize);
+                        }
+                        index += 1;
+                    }
+                } else if id == "rect" {
+                    env.complex_format = ComplexFormat::Rect;
+                } else if id == "polar" {
+                    env.complex_format = ComplexFormat::Polar;
+                } else if id == "i" {
+                    env.imaginary_unit = 'i';
+                } else if id == "j" {
+                    env.imaginary_unit = 'j';
+                } else if id == "zero" {
+                    // format zero 1e-12 : threshold relative to the magnitude, 0 to disable.
+                    match arg.get(index + 1) {
+                        Some(Token::FNum(f)) => {
+                            env.zero_threshold = *f;
+                            index += 1;
+                        }
+                        Some(Token::Num(n)) => {
+                            env.zero_threshold = *n as f64;
+                            index += 1;
+                        }
+                        _ => {}
+                    }
                 }
             }
             _ => {}
         }
+        index += 1;
     }
     format!(
-        "format radix={} separate={} float={:?}",
-        env.output_radix, env.separate_digit, env.float_format,
+        "format radix={} word={} separate={} float={:?} precision={:?} ieee={} complex={:?} imaginary={} zero={}",
+        env.output_radix,
+        env.word_size,
+        env.separate_digit,
+        env.float_format,
+        env.float_precision,
+        env.ieee_bits,
+        env.complex_format,
+        env.imaginary_unit,
+        env.zero_threshold,
     )
 }
 
@@ -249,21 +1632,92 @@ fn separate_digit(s: String, sep: &str, n: usize) -> String {
     String::from_utf8(result).unwrap()
 }
 
+// Number of bits to represent `n` in two's complement (sign bit is included if negative).
+fn bit_length(n: i128) -> u32 {
+    if n < 0 {
+        129 - (!n).leading_zeros()
+    } else {
+        128 - n.leading_zeros()
+    }
+}
+
+// 255 | 0xff | 0o377 | 0b11111111 | u8 255 | i8 -1 | 8 bits
+fn output_format_all(env: &mut Env, n: i128) -> String {
+    let bits = bit_length(n);
+    let word = if env.word_size != 0 {
+        env.word_size
+    } else {
+        bits.max(8).next_power_of_two()
+    };
+    let unsigned = if word >= 128 {
+        n as u128
+    } else {
+        n as u128 & ((1u128 << word) - 1)
+    };
+    let signed = ((unsigned << (128 - word)) as i128) >> (128 - word);
+    let separate = |s: String| {
+        if env.separate_digit != 0 {
+            separate_digit(s, "_", env.separate_digit)
+        } else {
+            s
+        }
+    };
+    format!(
+        "{} | 0x{} | 0o{} | 0b{} | u{} {} | i{} {} | {} bits",
+        separate(format!("{}", n)),
+        separate(format!("{:x}", unsigned)),
+        separate(format!("{:o}", unsigned)),
+        separate(format!("{:b}", unsigned)),
+        word,
+        unsigned,
+        word,
+        signed,
+        bits
+    )
+}
+
+// Digits of `n` in `radix` (2..=36), lower case.
+fn format_radix(mut n: u128, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit((n % radix as u128) as u32, radix).unwrap());
+        n /= radix as u128;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
 pub fn output_format_num(env: &mut Env, n: i128) -> String {
     let mut num_string: String;
 
+    if env.output_radix == 0 {
+        return output_format_all(env, n);
+    }
+
+    // Negative number is two's complement of `format word` bits, i.e. 0xc000 of Q1.15 -0.5.
+    let bits = if n < 0 && env.word_size != 0 && env.word_size < 128 {
+        n & ((1i128 << env.word_size) - 1)
+    } else {
+        n
+    };
+
     match env.output_radix {
         2 => {
-            num_string = format!("{:b}", n);
+            num_string = format!("{:b}", bits);
         }
         10 => {
             num_string = format!("{}", n);
         }
+        8 => {
+            num_string = format!("{:o}", bits);
+        }
         16 => {
-            num_string = format!("{:x}", n);
+            num_string = format!("{:x}", bits);
         }
-        _ => {
-            num_string = format!("{:?}", n);
+        radix => {
+            num_string = format_radix(n.unsigned_abs(), radix as u32);
         }
     }
 
@@ -275,20 +1729,115 @@ pub fn output_format_num(env: &mut Env, n: i128) -> String {
         2 => {
             num_string = format!("0b{}", num_string);
         }
+        8 => {
+            num_string = format!("0o{}", num_string);
+        }
+        10 => {}
         16 => {
             num_string = format!("0x{}", num_string);
         }
-        _ => {}
+        radix => {
+            // -36#zz
+            let sign = if n < 0 { "-" } else { "" };
+            num_string = format!("{}{}#{}", sign, radix, num_string);
+        }
     }
 
     num_string
 }
 
+// 1.5 -> 0x3ff8000000000000 sign 0 exponent 0x3ff (2^0) mantissa 0x8000000000000
+pub fn output_format_ieee(env: &mut Env, f: f64) -> String {
+    let (bits, width, exponent_bits, mantissa_bits) = if env.ieee_bits == 32 {
+        ((f as f32).to_bits() as u64, 8, 8, 23)
+    } else {
+        (f.to_bits(), 16, 11, 52)
+    };
+    let sign = bits >> (exponent_bits + mantissa_bits);
+    let exponent_max = (1 << exponent_bits) - 1;
+    let exponent = (bits >> mantissa_bits) & exponent_max;
+    let mantissa = bits & ((1 << mantissa_bits) - 1);
+    let power = if exponent == 0 {
+        "zero/subnormal".to_owned()
+    } else if exponent == exponent_max {
+        "inf/nan".to_owned()
+    } else {
+        format!("2^{}", exponent as i64 - (exponent_max >> 1) as i64)
+    };
+    format!(
+        "0x{:0width$x} sign {} exponent 0x{:x} ({}) mantissa 0x{:x}",
+        bits,
+        sign,
+        exponent,
+        power,
+        mantissa,
+        width = width
+    )
+}
+
+// Format non-negative `f` with `precision`.
+fn format_precision(precision: &FloatPrecision, f: f64) -> String {
+    match precision {
+        FloatPrecision::Shortest => format!("{}", f),
+        FloatPrecision::Decimals(n) => format!("{:.*}", n, f),
+        FloatPrecision::Digits(n) => {
+            if f == 0.0 {
+                return format!("{:.*}", n - 1, f);
+            }
+            let exponent = f.log10().floor() as i32;
+            let s = format_digits(*n, exponent, f);
+            // Re-round when rounding carries to the next digit, i.e. 9.99996 (4 digits) => 10.00
+            if s.parse::<f64>().unwrap_or(0.0) >= 10.0_f64.powi(exponent + 1) {
+                format_digits(*n, exponent + 1, f)
+            } else {
+                s
+            }
+        }
+    }
+}
+
+// Format `f` with `n` significant digits, `exponent` is the position of the first digit.
+fn format_digits(n: usize, exponent: i32, f: f64) -> String {
+    let decimals = n as i32 - 1 - exponent;
+    if decimals >= 0 {
+        format!("{:.*}", decimals as usize, f)
+    } else {
+        // 123456 (3 digits) => 123000
+        let scale = 10.0_f64.powi(-decimals);
+        format!("{:.0}", (f / scale).round() * scale)
+    }
+}
+
+// Format mantissa of Sci/Eng format. Carry to the exponent when rounding reaches `limit`,
+// i.e. 9.9996 (3 digits) => 1.00e1
+fn format_mantissa(
+    env: &Env,
+    mantissa: f64,
+    exponent: i32,
+    limit: f64,
+    step: i32,
+) -> (String, i32) {
+    let s = format_precision(&env.float_precision, mantissa);
+    if s.parse::<f64>().unwrap_or(0.0) >= limit {
+        (format_precision(&env.float_precision, 1.0), exponent + step)
+    } else {
+        (s, exponent)
+    }
+}
+
 pub fn output_format_float(env: &mut Env, f: f64) -> String {
+    if f < 0.0 {
+        return format!("-{}", output_format_float(env, -f));
+    }
+    if f == 0.0 {
+        // -0.0 is printed as 0
+        return format_precision(&env.float_precision, 0.0);
+    }
+    if !f.is_finite() {
+        return format!("{}", f);
+    }
     match env.float_format {
-        FloatFormat::Fix => {
-            format!("{}", f)
-        }
+        FloatFormat::Fix => format_precision(&env.float_precision, f),
         FloatFormat::Sci => {
             let mut exponent = 0;
             let mut mantissa = f;
@@ -300,6 +1849,7 @@ pub fn output_format_float(env: &mut Env, f: f64) -> String {
                 mantissa *= 10.0;
                 exponent -= 1;
             }
+            let (mantissa, exponent) = format_mantissa(env, mantissa, exponent, 10.0, 1);
             format!("{}e{}", mantissa, exponent)
         }
         FloatFormat::Eng => {
@@ -313,8 +1863,9 @@ pub fn output_format_float(env: &mut Env, f: f64) -> String {
                 mantissa *= 1000.0;
                 exponent -= 3;
             }
+            let (mantissa, exponent) = format_mantissa(env, mantissa, exponent, 1000.0, 3);
             if exponent == 0 {
-                format!("{}", mantissa)
+                mantissa
             } else if exponent == 3 {
                 format!("{}k", mantissa)
             } else if exponent == 6 {
@@ -338,6 +1889,41 @@ pub fn output_format_float(env: &mut Env, f: f64) -> String {
     }
 }
 
+// Clear the component which is negligible compared to the magnitude,
+// i.e. exp(i*pi) = -1+0.00000000000000012246467991473532i => -1+0i
+fn complex_cleanup(env: &Env, c: Complex64) -> Complex64 {
+    let threshold = c.norm() * env.zero_threshold;
+    Complex64::new(
+        if c.re.abs() < threshold { 0.0 } else { c.re },
+        if c.im.abs() < threshold { 0.0 } else { c.im },
+    )
+}
+
+/// Rect: a+bi, each component is formatted by `output_format_float()`.
+/// Polar: magnitude∠angle, angle is in the current angle mode.
+pub fn output_format_complex(env: &mut Env, c: Complex64) -> String {
+    let c = complex_cleanup(env, c);
+    match env.complex_format {
+        ComplexFormat::Rect => {
+            let re = output_format_float(env, c.re);
+            let im = output_format_float(env, c.im.abs());
+            if c.im.is_sign_negative() {
+                format!("{}-{}{}", re, im, env.imaginary_unit)
+            } else {
+                format!("{}+{}{}", re, im, env.imaginary_unit)
+            }
+        }
+        ComplexFormat::Polar => {
+            let angle = c.arg() / env.angle_mode.radian();
+            format!(
+                "{}∠{}",
+                output_format_float(env, c.norm()),
+                output_format_float(env, angle)
+            )
+        }
+    }
+}
+
 fn output_format_units_sub(_env: &mut Env, units: &HashMap<String, i32>) -> String {
     let mut vec: Vec<(&String, &i32)> = units.iter().collect();
     vec.sort_by(|a, b| a.0.cmp(b.0));
@@ -359,7 +1945,7 @@ pub fn output_format_units(env: &mut Env, units: Node) -> String {
     if let Node::Units(ref uuu) = units {
         if let Node::UnitsFraction(numerator, denominator) = &**uuu {
             if denominator.is_empty() {
-                if numerator.is_empty() {
+                if numerator.keys().all(|key| key == "_") {
                     return "".to_string();
                 } else {
                     return format!("[{}]", output_format_units_sub(env, numerator));
@@ -381,6 +1967,93 @@ pub fn output_format_units(env: &mut Env, units: Node) -> String {
     format!("[{:?}]", units)
 }
 
+fn impl_angle(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_angle {:?}\r", arg);
+    }
+    match arg.first() {
+        Some(Token::Ident(id)) if arg.len() == 1 => {
+            if id == "rad" || id == "radian" {
+                env.angle_mode = AngleMode::Rad;
+            } else if id == "deg" || id == "degree" {
+                env.angle_mode = AngleMode::Deg;
+            } else if id == "grad" || id == "gradian" {
+                env.angle_mode = AngleMode::Grad;
+            } else {
+                return "angle should be: angle rad/deg/grad".to_owned();
+            }
+        }
+        None => {}
+        _ => return "angle should be: angle rad/deg/grad".to_owned(),
+    }
+    format!("angle {:?}", env.angle_mode)
+}
+
+// qmode even sat : rounding (round/even/floor/trunc) and overflow (sat/wrap) of q()
+fn impl_qmode(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_qmode {:?}\r", arg);
+    }
+    for token in arg {
+        if let Token::Ident(id) = token {
+            if id == "round" {
+                env.q_rounding = QRounding::Nearest;
+            } else if id == "even" {
+                env.q_rounding = QRounding::Even;
+            } else if id == "floor" {
+                env.q_rounding = QRounding::Floor;
+            } else if id == "trunc" {
+                env.q_rounding = QRounding::Trunc;
+            } else if id == "sat" || id == "saturate" {
+                env.q_saturate = true;
+            } else if id == "wrap" {
+                env.q_saturate = false;
+            }
+        }
+    }
+    format!(
+        "qmode rounding={:?} saturate={}",
+        env.q_rounding, env.q_saturate
+    )
+}
+
+/// Format an evaluated value with its units, i.e. `1.5k [m/s]`.
+/// This is the common formatter of REPL, script, command line expression and variable listing.
+pub fn output_format(env: &mut Env, node: &Node) -> String {
+    if let Node::List(items) = node {
+        let items: Vec<String> = items.iter().map(|item| output_format(env, item)).collect();
+        return format!("{{{}}}", items.join(", "));
+    }
+    if let Node::Matrix(rows) = node {
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|row| output_format(env, &Node::List(row.clone())))
+            .collect();
+        return format!("{{{}}}", rows.join(", "));
+    }
+    if let Node::TransferFn(num, den) = node {
+        return output_format_tf(env, num, den);
+    }
+    let (value, units) = match node {
+        Node::Num(n, units) => (output_format_num(env, *n), units),
+        Node::FNum(f, units) => (output_format_float(env, *f), units),
+        Node::CNum(c, units) => (output_format_complex(env, *c), units),
+        _ => return String::new(),
+    };
+    let units = output_format_units(env, (**units).clone());
+    let value = if units.is_empty() {
+        value
+    } else {
+        format!("{} {}", value, units)
+    };
+    match node {
+        Node::FNum(f, _) if env.ieee_bits != 0 => {
+            format!("{} | {}", value, output_format_ieee(env, *f))
+        }
+        _ => value,
+    }
+}
+
 fn impl_debug(env: &mut Env, arg: &[Token]) -> String {
     if env.is_debug() {
         eprintln!("impl_debug {:?}\r", arg);
@@ -407,6 +2080,33 @@ fn impl_debug(env: &mut Env, arg: &[Token]) -> String {
     format!("debug {}", env.debug)
 }
 
+// octal 1 : 011 is octal 9 (C style), octal 0 : 011 is decimal 11. 0o11 is always octal.
+fn impl_octal(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_octal {:?}\r", arg);
+    }
+    if arg.is_empty() {
+        return format!("octal {}", env.leading_zero_octal);
+    }
+    match &arg[0] {
+        Token::Num(0) => {
+            env.leading_zero_octal = false;
+        }
+        Token::Num(1) => {
+            env.leading_zero_octal = true;
+        }
+        Token::Ident(id) => {
+            if id == "on" || id == "true" {
+                env.leading_zero_octal = true;
+            } else if id == "off" || id == "false" {
+                env.leading_zero_octal = false;
+            }
+        }
+        _ => {}
+    }
+    format!("octal {}", env.leading_zero_octal)
+}
+
 fn impl_exit(env: &mut Env, arg: &[Token]) -> String {
     if env.is_debug() {
         eprintln!("impl_exit {:?}\r", arg);
@@ -433,24 +2133,159 @@ fn impl_defun(env: &mut Env, arg: &[Token]) -> String {
     String::from("")
 }
 
+fn register_layout(name: &str, fields: &[(String, u32, u32)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(field, msb, lsb)| {
+            if msb == lsb {
+                format!("{}[{}]", field, msb)
+            } else {
+                format!("{}[{}:{}]", field, msb, lsb)
+            }
+        })
+        .collect::<Vec<String>>();
+    format!("{}: {}", name, fields.join(" "))
+}
+
+// defreg ctrl DIV 15 8 MODE 3 1 EN 0 : define register layout, field msb [lsb]
+fn impl_defreg(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_defreg {:?}\r", arg);
+    }
+    let name = match arg.first() {
+        Some(Token::Ident(name)) => name.clone(),
+        _ => {
+            let mut ret = String::new();
+            let mut names: Vec<&String> = env.register.keys().collect();
+            names.sort();
+            for name in names {
+                ret.push_str(&register_layout(name, &env.register[name]));
+                ret.push_str("\r\n");
+            }
+            return ret;
+        }
+    };
+    if arg.len() == 1 {
+        return match env.register.get(&name) {
+            Some(fields) => register_layout(&name, fields),
+            None => format!("defreg: unknown register {}", name),
+        };
+    }
+    let mut fields = Vec::new();
+    let mut index = 1;
+    while index < arg.len() {
+        match (&arg[index], arg.get(index + 1), arg.get(index + 2)) {
+            (Token::Ident(field), Some(Token::Num(msb)), Some(Token::Num(lsb))) => {
+                fields.push((field.clone(), *msb, *lsb));
+                index += 3;
+            }
+            (Token::Ident(field), Some(Token::Num(msb)), _) => {
+                fields.push((field.clone(), *msb, *msb));
+                index += 2;
+            }
+            _ => {
+                return "defreg should be: defreg name field msb [lsb] ...".to_owned();
+            }
+        }
+    }
+    let mut layout = Vec::new();
+    for (field, msb, lsb) in fields {
+        if lsb < 0 || msb < lsb || msb >= 128 {
+            return format!("defreg: bad bit range of {}", field);
+        }
+        layout.push((field, msb as u32, lsb as u32));
+    }
+    layout.sort_by_key(|field| std::cmp::Reverse(field.1));
+    let ret = register_layout(&name, &layout);
+    env.register.insert(name, layout);
+    ret
+}
+
+// decode ctrl 0x8305 : print value field by field, value is an expression.
+fn impl_decode(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_decode {:?}\r", arg);
+    }
+    let (name, fields) = match arg.first() {
+        Some(Token::Ident(name)) => match env.register.get(name) {
+            Some(fields) => (name.clone(), fields.clone()),
+            None => return format!("decode: unknown register {}", name),
+        },
+        _ => return "decode should be: decode register value".to_owned(),
+    };
+    let value = match parse(env, &arg[1..]).and_then(|node| eval(env, &node)) {
+        Ok(Node::Num(n, _)) => n,
+        Ok(_) => return "decode: value should be integer".to_owned(),
+        Err(e) => return format!("decode: {}", e),
+    };
+    let mut ret = format!("{} = {}\r\n", name, output_format_num(env, value));
+    for (field, msb, lsb) in fields {
+        let bits = ((value as u128 >> lsb) & bit_mask(msb - lsb + 1)) as i128;
+        let range = if msb == lsb {
+            format!("{}", msb)
+        } else {
+            format!("{}:{}", msb, lsb)
+        };
+        ret.push_str(&format!(
+            "  {}[{}] = {}\r\n",
+            field,
+            range,
+            output_format_num(env, bits)
+        ));
+    }
+    ret
+}
+
+// factor 360 : 360 = 2^3 * 3^2 * 5, value is an expression.
+fn impl_factor(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_factor {:?}\r", arg);
+    }
+    let n = match parse(env, arg).and_then(|node| eval(env, &node)) {
+        Ok(node) => match int_value(&node) {
+            Some(n) => n,
+            None => return "factor: value should be integer".to_owned(),
+        },
+        Err(e) => return format!("factor: {}", e),
+    };
+    if n.unsigned_abs() < 2 {
+        return format!("{} = {}", n, n);
+    }
+    let mut factors = Vec::new();
+    if !factorize(n.unsigned_abs(), &mut factors, &mut 0) {
+        return format!("factor: {} has a factor too large to find", n);
+    }
+    factors.sort_unstable();
+    let mut product = Vec::new();
+    if n < 0 {
+        product.push("-1".to_owned());
+    }
+    let mut index = 0;
+    while index < factors.len() {
+        let count = factors[index..]
+            .iter()
+            .take_while(|p| **p == factors[index])
+            .count();
+        if count == 1 {
+            product.push(format!("{}", factors[index]));
+        } else {
+            product.push(format!("{}^{}", factors[index], count));
+        }
+        index += count;
+    }
+    format!("{} = {}", n, product.join(" * "))
+}
+
 fn print_var(env: &mut Env, key: &str, n: &Node) -> String {
     if let Ok(n) = eval(env, n) {
         match n {
-            Node::Num(_, _) => {
-                // TODO: print units
-                if let Ok(value) = eval_fvalue(env, &n) {
-                    return format!("{} = {}\r\n", key, value);
-                }
-            }
-            Node::FNum(_, _) => {
-                if let Ok(value) = eval_fvalue(env, &n) {
-                    return format!("{} = {}\r\n", key, value);
-                }
-            }
-            Node::CNum(_, _) => {
-                if let Ok(value) = eval_cvalue(env, &n) {
-                    return format!("{} = {}\r\n", key, value);
-                }
+            Node::Num(_, _)
+            | Node::FNum(_, _)
+            | Node::CNum(_, _)
+            | Node::List(_)
+            | Node::Matrix(_)
+            | Node::TransferFn(_, _) => {
+                return format!("{} = {}\r\n", key, output_format(env, &n));
             }
             _ => {}
         }
@@ -485,7 +2320,7 @@ fn impl_func(env: &mut Env, arg: &[Token]) -> String {
         eprintln!("impl_func {:?}\r", arg);
     }
     let mut ret = String::new();
-    for key in env.func.keys() {
+    for key in env.func.keys().chain(env.lazy_func.keys()) {
         ret.push_str(format!("{}\r\n", key).as_str());
     }
     ret
@@ -561,15 +2396,29 @@ impl<'a> Env<'a> {
             constant: HashMap::new(),
             variable: HashMap::new(),
             func: HashMap::new(),
+            lazy_func: HashMap::new(),
+            list_func: HashSet::new(),
             user_func: HashMap::new(),
             cmd: HashMap::new(),
             debug: false,
             output_radix: 10,
+            word_size: 0,
+            leading_zero_octal: true,
+            ieee_bits: 0,
+            q_rounding: QRounding::Nearest,
+            q_saturate: true,
+            register: HashMap::new(),
             separate_digit: 0,
             float_format: FloatFormat::Fix,
+            float_precision: FloatPrecision::Shortest,
+            complex_format: ComplexFormat::Rect,
+            imaginary_unit: 'i',
+            zero_threshold: 1e-12,
+            angle_mode: AngleMode::Rad,
             history_path: path::PathBuf::new(),
             history_max: 0,
             history_index: 0,
+            parse_units: false,
             history: Vec::new(),
         }
     }
@@ -614,18 +2463,206 @@ impl<'a> Env<'a> {
         self.func.insert("arg", (impl_arg as TypeFn, 1));
         self.func.insert("max", (impl_max as TypeFn, 0));
         self.func.insert("ave", (impl_ave as TypeFn, 0));
+        self.func.insert("min", (impl_min as TypeFn, 0));
+        self.func.insert("sum", (impl_sum as TypeFn, 0));
+        self.func.insert("product", (impl_product as TypeFn, 0));
+        self.func.insert("prod", (impl_product as TypeFn, 0));
+        self.func.insert("median", (impl_median as TypeFn, 0));
+        self.func.insert("mode", (impl_mode as TypeFn, 0));
+        self.func.insert("var", (impl_var as TypeFn, 0));
+        self.func.insert("varp", (impl_varp as TypeFn, 0));
+        self.func.insert("std", (impl_std as TypeFn, 0));
+        self.func.insert("stdp", (impl_stdp as TypeFn, 0));
+        self.func.insert("rms", (impl_rms as TypeFn, 0));
+        self.func.insert("geomean", (impl_geomean as TypeFn, 0));
+        self.func.insert("harmmean", (impl_harmmean as TypeFn, 0));
+        self.func
+            .insert("percentile", (impl_percentile as TypeFn, 0));
         self.func.insert("sqrt", (impl_sqrt as TypeFn, 1));
         self.func.insert("E12", (impl_e12 as TypeFn, 1));
+        self.func.insert("tan", (impl_tan as TypeFn, 1));
+        self.func.insert("asin", (impl_asin as TypeFn, 1));
+        self.func.insert("acos", (impl_acos as TypeFn, 1));
+        self.func.insert("atan", (impl_atan as TypeFn, 1));
+        self.func.insert("atan2", (impl_atan2 as TypeFn, 2));
+        self.func.insert("polar", (impl_polar as TypeFn, 2));
+        self.func.insert("deg", (impl_deg as TypeFn, 1));
+        self.func.insert("rad", (impl_rad as TypeFn, 1));
+        self.func.insert("sinh", (impl_sinh as TypeFn, 1));
+        self.func.insert("cosh", (impl_cosh as TypeFn, 1));
+        self.func.insert("tanh", (impl_tanh as TypeFn, 1));
+        self.func.insert("asinh", (impl_asinh as TypeFn, 1));
+        self.func.insert("acosh", (impl_acosh as TypeFn, 1));
+        self.func.insert("atanh", (impl_atanh as TypeFn, 1));
+        self.func.insert("ln", (impl_ln as TypeFn, 1));
+        self.func.insert("log10", (impl_log10 as TypeFn, 1));
+        self.func.insert("log2", (impl_log2 as TypeFn, 1));
+        self.func.insert("log", (impl_log as TypeFn, 2));
+        self.func.insert("pow2db", (impl_pow2db as TypeFn, 1));
+        self.func.insert("db2pow", (impl_db2pow as TypeFn, 1));
+        self.func.insert("mag2db", (impl_mag2db as TypeFn, 1));
+        self.func.insert("db2mag", (impl_db2mag as TypeFn, 1));
+        self.func.insert("w2dbm", (impl_w2dbm as TypeFn, 1));
+        self.func.insert("dbm2w", (impl_dbm2w as TypeFn, 1));
+        self.func.insert("w2dbw", (impl_w2dbw as TypeFn, 1));
+        self.func.insert("dbw2w", (impl_dbw2w as TypeFn, 1));
+        self.func.insert("v2dbv", (impl_v2dbv as TypeFn, 1));
+        self.func.insert("dbv2v", (impl_dbv2v as TypeFn, 1));
+        self.func.insert("v2dbu", (impl_v2dbu as TypeFn, 1));
+        self.func.insert("dbu2v", (impl_dbu2v as TypeFn, 1));
+        self.func.insert("mag2np", (impl_mag2np as TypeFn, 1));
+        self.func.insert("np2mag", (impl_np2mag as TypeFn, 1));
+        self.func.insert("np2db", (impl_np2db as TypeFn, 1));
+        self.func.insert("db2np", (impl_db2np as TypeFn, 1));
+        self.func.insert("f64bits", (impl_f64bits as TypeFn, 1));
+        self.func.insert("f32bits", (impl_f32bits as TypeFn, 1));
+        self.func.insert("fsign", (impl_fsign as TypeFn, 1));
+        self.func.insert("fexp", (impl_fexp as TypeFn, 1));
+        self.func.insert("fmant", (impl_fmant as TypeFn, 1));
+        self.func.insert("bits2f64", (impl_bits2f64 as TypeFn, 1));
+        self.func.insert("bits2f32", (impl_bits2f32 as TypeFn, 1));
+        self.func.insert("q", (impl_q as TypeFn, 3));
+        self.func.insert("fromq", (impl_fromq as TypeFn, 3));
+        self.func.insert("qerr", (impl_qerr as TypeFn, 3));
+        self.func.insert("bits", (impl_bits as TypeFn, 3));
+        self.func.insert("insbits", (impl_insbits as TypeFn, 4));
+        self.func.insert("bset", (impl_bset as TypeFn, 2));
+        self.func.insert("bclr", (impl_bclr as TypeFn, 2));
+        self.func.insert("btest", (impl_btest as TypeFn, 2));
+        self.func.insert("popcount", (impl_popcount as TypeFn, 0));
+        self.func.insert("clz", (impl_clz as TypeFn, 0));
+        self.func.insert("ctz", (impl_ctz as TypeFn, 0));
+        self.func.insert("bswap", (impl_bswap as TypeFn, 0));
+        self.func.insert("bitrev", (impl_bitrev as TypeFn, 0));
+        self.func.insert("floor", (impl_floor as TypeFn, 1));
+        self.func.insert("ceil", (impl_ceil as TypeFn, 1));
+        self.func.insert("round", (impl_round as TypeFn, 0));
+        self.func.insert("trunc", (impl_trunc as TypeFn, 1));
+        self.func.insert("roundsig", (impl_roundsig as TypeFn, 2));
+        self.func.insert("mround", (impl_mround as TypeFn, 2));
+        self.func.insert("sign", (impl_sign as TypeFn, 1));
+        self.func.insert("frac", (impl_frac as TypeFn, 1));
+        self.func.insert("int", (impl_int as TypeFn, 1));
+        self.func.insert("gcd", (impl_gcd as TypeFn, 0));
+        self.func.insert("lcm", (impl_lcm as TypeFn, 0));
+        self.func.insert("modpow", (impl_modpow as TypeFn, 3));
+        self.func.insert("modinv", (impl_modinv as TypeFn, 2));
+        self.func.insert("isprime", (impl_isprime as TypeFn, 1));
+        self.func.insert("nextprime", (impl_nextprime as TypeFn, 1));
+        self.func.insert("fact", (impl_fact as TypeFn, 1));
+        self.func.insert("binom", (impl_binom as TypeFn, 2));
+        self.func.insert("isqrt", (impl_isqrt as TypeFn, 1));
+        self.func.insert("transpose", (impl_transpose as TypeFn, 1));
+        self.func.insert("det", (impl_det as TypeFn, 1));
+        self.func.insert("inv", (impl_inv as TypeFn, 1));
+        self.func.insert("linsolve", (impl_linsolve as TypeFn, 0));
+        self.func.insert("eig", (impl_eig as TypeFn, 1));
+        self.func.insert("roots", (impl_roots as TypeFn, 0));
+        self.func.insert("tf", (impl_tf as TypeFn, 0));
+        self.func.insert("freqresp", (impl_freqresp as TypeFn, 2));
+        self.func.insert("bodemag", (impl_bodemag as TypeFn, 2));
+        self.func.insert("bodephase", (impl_bodephase as TypeFn, 2));
+        self.func.insert("bandwidth", (impl_bandwidth as TypeFn, 1));
+        self.func.insert("gm", (impl_gm as TypeFn, 1));
+        self.func.insert("pm", (impl_pm as TypeFn, 1));
+        self.func.insert("poles", (impl_poles as TypeFn, 1));
+        self.func.insert("zeros", (impl_zeros as TypeFn, 1));
+        self.func.insert("range", (impl_range as TypeFn, 0));
+        self.func.insert("linspace", (impl_linspace as TypeFn, 3));
+        self.func.insert("logspace", (impl_logspace as TypeFn, 3));
+        self.list_func.insert("max");
+        self.list_func.insert("ave");
+        self.list_func.insert("min");
+        self.list_func.insert("sum");
+        self.list_func.insert("product");
+        self.list_func.insert("prod");
+        self.list_func.insert("median");
+        self.list_func.insert("mode");
+        self.list_func.insert("var");
+        self.list_func.insert("varp");
+        self.list_func.insert("std");
+        self.list_func.insert("stdp");
+        self.list_func.insert("rms");
+        self.list_func.insert("geomean");
+        self.list_func.insert("harmmean");
+        self.list_func.insert("percentile");
+        self.list_func.insert("gcd");
+        self.list_func.insert("lcm");
+        self.list_func.insert("linsolve");
+        self.list_func.insert("roots");
+        self.list_func.insert("tf");
+        self.lazy_func.insert("map", (impl_map as TypeLazyFn, 2));
+        self.lazy_func
+            .insert("filter", (impl_filter as TypeLazyFn, 2));
+        self.lazy_func
+            .insert("solve", (impl_solve as TypeLazyFn, 0));
+        self.lazy_func
+            .insert("integrate", (impl_integrate as TypeLazyFn, 0));
+        self.lazy_func.insert("diff", (impl_diff as TypeLazyFn, 0));
+        self.lazy_func
+            .insert("minimize", (impl_minimize as TypeLazyFn, 0));
+        self.lazy_func
+            .insert("maximize", (impl_maximize as TypeLazyFn, 0));
         self.cmd.insert(
             "format",
             (impl_output_format as TypeCmd, 0, "set output format"),
         );
+        self.cmd.insert(
+            "angle",
+            (impl_angle as TypeCmd, 1, "set angle mode (rad/deg/grad)"),
+        );
+        self.cmd.insert(
+            "qmode",
+            (
+                impl_qmode as TypeCmd,
+                0,
+                "set rounding (round/even/floor/trunc) and overflow (sat/wrap) of q()",
+            ),
+        );
         self.cmd
             .insert("debug", (impl_debug as TypeCmd, 1, "set/reset debug mode"));
+        self.cmd.insert(
+            "octal",
+            (
+                impl_octal as TypeCmd,
+                1,
+                "leading zero is octal (1) or decimal (0)",
+            ),
+        );
         self.cmd
             .insert("exit", (impl_exit as TypeCmd, 0, "exit REPL"));
         self.cmd
             .insert("defun", (impl_defun as TypeCmd, 0, "define user function"));
+        self.cmd
+            .insert("factor", (impl_factor as TypeCmd, 0, "prime factorization"));
+        self.cmd.insert(
+            "derive",
+            (
+                impl_derive as TypeCmd,
+                0,
+                "symbolic derivative: derive x expr",
+            ),
+        );
+        self.cmd.insert(
+            "ode",
+            (
+                impl_ode as TypeCmd,
+                0,
+                "solve ODE: ode f, y0, t0, t1[, points]",
+            ),
+        );
+        self.cmd.insert(
+            "simplify",
+            (impl_simplify as TypeCmd, 0, "simplify expression"),
+        );
+        self.cmd.insert(
+            "defreg",
+            (impl_defreg as TypeCmd, 0, "define register layout"),
+        );
+        self.cmd.insert(
+            "decode",
+            (impl_decode as TypeCmd, 0, "decode value by register layout"),
+        );
         self.cmd
             .insert("constant", (impl_constant as TypeCmd, 0, "list constants"));
         self.cmd.insert(
@@ -680,6 +2717,10 @@ impl<'a> Env<'a> {
         self.cmd.get(key).copied()
     }
 
+    pub fn is_lazy_func(&self, key: &str) -> Option<(TypeLazyFn, usize)> {
+        self.lazy_func.get(key).copied()
+    }
+
     fn new_user_func(&mut self, key: String, arg: &[Token]) {
         self.user_func.insert(key, arg.to_vec());
     }
@@ -719,6 +2760,141 @@ mod tests {
         );
     }
     #[test]
+    fn test_format_radix() {
+        let mut env = Env::new();
+        env.output_radix = 8;
+        assert_eq!(output_format_num(&mut env, 9), "0o11".to_owned());
+        env.output_radix = 36;
+        assert_eq!(output_format_num(&mut env, 1295), "36#zz".to_owned());
+        assert_eq!(output_format_num(&mut env, -1295), "-36#zz".to_owned());
+        env.output_radix = 3;
+        assert_eq!(output_format_num(&mut env, 0), "3#0".to_owned());
+        env.separate_digit = 4;
+        assert_eq!(output_format_num(&mut env, 242), "3#2_2222".to_owned());
+    }
+    #[test]
+    fn test_format_ieee() {
+        let mut env = Env::new();
+        env.ieee_bits = 64;
+        assert_eq!(
+            output_format_ieee(&mut env, 1.5),
+            "0x3ff8000000000000 sign 0 exponent 0x3ff (2^0) mantissa 0x8000000000000".to_owned()
+        );
+        assert_eq!(
+            output_format_ieee(&mut env, -0.0),
+            "0x8000000000000000 sign 1 exponent 0x0 (zero/subnormal) mantissa 0x0".to_owned()
+        );
+        env.ieee_bits = 32;
+        assert_eq!(
+            output_format_ieee(&mut env, 0.1),
+            "0x3dcccccd sign 0 exponent 0x7b (2^-4) mantissa 0x4ccccd".to_owned()
+        );
+        assert_eq!(
+            output_format_ieee(&mut env, f64::INFINITY),
+            "0x7f800000 sign 0 exponent 0xff (inf/nan) mantissa 0x0".to_owned()
+        );
+    }
+    #[test]
+    fn test_format_word() {
+        let mut env = Env::new();
+        env.output_radix = 16;
+        env.word_size = 16;
+        assert_eq!(output_format_num(&mut env, -16384), "0xc000".to_owned());
+        assert_eq!(output_format_num(&mut env, 16384), "0x4000".to_owned());
+        env.output_radix = 10;
+        assert_eq!(output_format_num(&mut env, -16384), "-16384".to_owned());
+    }
+    #[test]
+    fn test_register() {
+        let mut env = Env::new();
+        env.built_in();
+        assert_eq!(
+            impl_defreg(
+                &mut env,
+                &lexer("ctrl EN 0 MODE 3 1 DIV 15 8".to_owned()).unwrap()
+            ),
+            "ctrl: DIV[15:8] MODE[3:1] EN[0]".to_owned()
+        );
+        env.output_radix = 16;
+        assert_eq!(
+            impl_decode(&mut env, &lexer("ctrl 0x8300+5".to_owned()).unwrap()),
+            "ctrl = 0x8305\r\n  DIV[15:8] = 0x83\r\n  MODE[3:1] = 0x2\r\n  EN[0] = 0x1\r\n"
+                .to_owned()
+        );
+        assert_eq!(
+            impl_decode(&mut env, &lexer("status 1".to_owned()).unwrap()),
+            "decode: unknown register status".to_owned()
+        );
+        assert_eq!(
+            impl_defreg(&mut env, &lexer("bad F 1 2".to_owned()).unwrap()),
+            "defreg: bad bit range of F".to_owned()
+        );
+    }
+    #[test]
+    fn test_angle() {
+        let mut env = Env::new();
+        env.built_in();
+        let mut angle = |input: &str| impl_angle(&mut env, &lexer(input.to_owned()).unwrap());
+        assert_eq!(angle("deg"), "angle Deg".to_owned());
+        assert_eq!(angle(""), "angle Deg".to_owned());
+        assert_eq!(
+            angle("turn"),
+            "angle should be: angle rad/deg/grad".to_owned()
+        );
+        assert_eq!(
+            angle("rad 1"),
+            "angle should be: angle rad/deg/grad".to_owned()
+        );
+        assert_eq!(angle("radian"), "angle Rad".to_owned());
+    }
+    #[test]
+    fn test_factor() {
+        let mut env = Env::new();
+        env.built_in();
+        let mut factor = |input: &str| impl_factor(&mut env, &lexer(input.to_owned()).unwrap());
+        assert_eq!(factor("360"), "360 = 2^3 * 3^2 * 5".to_owned());
+        assert_eq!(factor("-97"), "-97 = -1 * 97".to_owned());
+        assert_eq!(factor("1"), "1 = 1".to_owned());
+        assert_eq!(
+            factor("600851475143"),
+            "600851475143 = 71 * 839 * 1471 * 6857".to_owned()
+        );
+        assert_eq!(
+            factor("1000000007*998244353"),
+            "998244359987710471 = 998244353 * 1000000007".to_owned()
+        );
+        assert_eq!(factor("1.5"), "factor: value should be integer".to_owned());
+        assert_eq!(
+            factor("4611686018427387847*4611686018427387817"),
+            "factor: 21267647932558653302378126310941659999 has a factor too large to find"
+                .to_owned()
+        );
+    }
+    #[test]
+    fn test_format_all() {
+        let mut env = Env::new();
+        env.output_radix = 0;
+        assert_eq!(
+            output_format_num(&mut env, 255),
+            "255 | 0xff | 0o377 | 0b11111111 | u8 255 | i8 -1 | 8 bits".to_owned()
+        );
+        assert_eq!(
+            output_format_num(&mut env, -1),
+            "-1 | 0xff | 0o377 | 0b11111111 | u8 255 | i8 -1 | 1 bits".to_owned()
+        );
+        assert_eq!(
+            output_format_num(&mut env, 256),
+            "256 | 0x100 | 0o400 | 0b100000000 | u16 256 | i16 256 | 9 bits".to_owned()
+        );
+        env.word_size = 16;
+        env.separate_digit = 4;
+        assert_eq!(
+            output_format_num(&mut env, -2),
+            "-2 | 0xfffe | 0o17_7776 | 0b1111_1111_1111_1110 | u16 65534 | i16 -2 | 2 bits"
+                .to_owned()
+        );
+    }
+    #[test]
     fn test_format_float() {
         let mut env = Env::new();
         assert_eq!(output_format_float(&mut env, 1.23), "1.23".to_owned());
@@ -726,6 +2902,70 @@ mod tests {
         assert_eq!(output_format_float(&mut env, 1e10), "1e10".to_owned());
         env.float_format = FloatFormat::Eng;
         assert_eq!(output_format_float(&mut env, 1e10), "10G".to_owned());
+        assert_eq!(output_format_float(&mut env, -2.2e-6), "-2.2u".to_owned());
+        assert_eq!(output_format_float(&mut env, 0.0), "0".to_owned());
+    }
+
+    #[test]
+    fn test_format_float_precision() {
+        let mut env = Env::new();
+        env.float_precision = FloatPrecision::Digits(4);
+        assert_eq!(
+            output_format_float(&mut env, std::f64::consts::PI),
+            "3.142".to_owned()
+        );
+        assert_eq!(
+            output_format_float(&mut env, 0.0012345),
+            "0.001234".to_owned()
+        );
+        assert_eq!(output_format_float(&mut env, 123456.0), "123500".to_owned());
+        assert_eq!(output_format_float(&mut env, -2.5), "-2.500".to_owned());
+        assert_eq!(output_format_float(&mut env, 9.99996), "10.00".to_owned());
+        assert_eq!(output_format_float(&mut env, 0.0), "0.000".to_owned());
+        env.float_precision = FloatPrecision::Decimals(3);
+        assert_eq!(output_format_float(&mut env, 0.0), "0.000".to_owned());
+        assert_eq!(output_format_float(&mut env, -0.0), "0.000".to_owned());
+        assert_eq!(
+            output_format_float(&mut env, std::f64::consts::PI),
+            "3.142".to_owned()
+        );
+        assert_eq!(output_format_float(&mut env, 2.0), "2.000".to_owned());
+
+        env.float_format = FloatFormat::Sci;
+        assert_eq!(output_format_float(&mut env, 12344.0), "1.234e4".to_owned());
+        env.float_precision = FloatPrecision::Digits(3);
+        assert_eq!(output_format_float(&mut env, 9.9996), "1.00e1".to_owned());
+
+        env.float_format = FloatFormat::Eng;
+        assert_eq!(output_format_float(&mut env, 4.7e-6), "4.70u".to_owned());
+        assert_eq!(output_format_float(&mut env, 999.96), "1.00k".to_owned());
+        assert_eq!(output_format_float(&mut env, 12345.0), "12.3k".to_owned());
+        env.float_precision = FloatPrecision::Decimals(1);
+        assert_eq!(output_format_float(&mut env, 395.0654), "395.1".to_owned());
+    }
+
+    #[test]
+    fn test_format_complex() {
+        let mut env = Env::new();
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(1.0, 2.0)),
+            "1+2i".to_owned()
+        );
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(-1.0, 1.2246467991473532e-16)),
+            "-1+0i".to_owned()
+        );
+        env.zero_threshold = 0.0;
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(-1.0, 1.2246467991473532e-16)),
+            "-1+0.00000000000000012246467991473532i".to_owned()
+        );
+        env.imaginary_unit = 'j';
+        env.float_format = FloatFormat::Eng;
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(1000.0, -2.2e-3)),
+            "1k-2.2mj".to_owned()
+        );
     }
 
     #[test]
@@ -15,6 +15,9 @@ pub enum TokenOp {
     Caret,          // ^ : power operator
     Comma,          // ,
     Equal,          // =
+    Angle,          // ∠ : polar complex, magnitude∠angle
+    BraceLeft,      // { : list
+    BraceRight,     // }
     None,
 }
 
@@ -77,6 +80,11 @@ fn tok_num_int(chars: &[char], index: usize) -> Result<(Token, usize), MyError>
                 err_str.push(chars[i]);
                 i += 1;
             }
+            'o' | 'O' => {
+                radix = 8;
+                err_str.push(chars[i]);
+                i += 1;
+            }
             '0'..='7' => {
                 radix = 8;
             }
@@ -104,6 +112,109 @@ fn tok_num_int(chars: &[char], index: usize) -> Result<(Token, usize), MyError>
         }
     }
 
+    if radix == 16 && i < chars.len() && (chars[i] == '.' || is_hex_exponent(chars, i)) {
+        return tok_hex_float(chars, i, mantissa, err_str);
+    }
+
+    match i128::from_str_radix(&mantissa, radix) {
+        Ok(int) => Ok((Token::Num(int), i)),
+        Err(e) => Err(MyError::LexerIntError(err_str, e)),
+    }
+}
+
+/// `p` is a binary exponent only when a signed digit follows, i.e. `0x1p-2`.
+/// Otherwise it is the pico postfix, i.e. `0x10p`.
+fn is_hex_exponent(chars: &[char], index: usize) -> bool {
+    let mut i = index;
+    if i >= chars.len() || (chars[i] != 'p' && chars[i] != 'P') {
+        return false;
+    }
+    i += 1;
+    if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+        i += 1;
+    }
+    i < chars.len() && chars[i].is_ascii_digit()
+}
+
+/// Eat fraction and binary exponent of hexadecimal float, i.e. `.8p3` of `0x1.8p3` (= 1.5 * 2^3).
+/// `mantissa` is the integer part already eaten.
+/// Return `Token::FNum()` with `Result<,Err(String)>`.
+/// Increment index and return as a member of tuple.
+fn tok_hex_float(
+    chars: &[char],
+    index: usize,
+    mut mantissa: String,
+    mut err_str: String,
+) -> Result<(Token, usize), MyError> {
+    let mut i = index;
+    let mut fraction_digits = 0;
+    let mut exponent = 0;
+    if chars[i] == '.' {
+        err_str.push(chars[i]);
+        i += 1;
+        while i < chars.len() {
+            match chars[i] {
+                '0'..='9' | 'a'..='f' | 'A'..='F' => {
+                    mantissa.push(chars[i]);
+                    err_str.push(chars[i]);
+                    fraction_digits += 1;
+                    i += 1;
+                }
+                '_' => {
+                    i += 1;
+                }
+                _ => {
+                    break;
+                }
+            }
+        }
+    }
+    if is_hex_exponent(chars, i) {
+        err_str.push(chars[i]);
+        i += 1;
+        if i < chars.len() && chars[i] == '+' {
+            i += 1;
+        }
+        let (a, b) = tok_get_num(chars, i);
+        err_str.push_str(&a);
+        i = b;
+        exponent = match a.parse::<i32>() {
+            Ok(exp) => exp,
+            Err(e) => return Err(MyError::LexerIntError(err_str, e)),
+        };
+    }
+    match u128::from_str_radix(&mantissa, 16) {
+        Ok(int) => Ok((
+            Token::FNum(int as f64 * 2f64.powi(exponent - 4 * fraction_digits)),
+            i,
+        )),
+        Err(e) => Err(MyError::LexerIntError(err_str, e)),
+    }
+}
+
+/// Eat integer of explicit `radix` (2..=36), i.e. `ZZ` of `36#ZZ`.
+/// `index` points the next of '#'.
+/// Return `Token::Num()` with `Result<,Err(String)>`.
+/// Increment index and return as a member of tuple.
+fn tok_num_radix(chars: &[char], index: usize, radix: u32) -> Result<(Token, usize), MyError> {
+    let mut i = index;
+    let mut mantissa = String::new();
+    let mut err_str = format!("{}#", radix);
+    while i < chars.len() {
+        match chars[i] {
+            '0'..='9' | 'a'..='z' | 'A'..='Z' => {
+                mantissa.push(chars[i]);
+                err_str.push(chars[i]);
+                i += 1;
+            }
+            '_' => {
+                i += 1;
+            }
+            _ => {
+                break;
+            }
+        }
+    }
     match i128::from_str_radix(&mantissa, radix) {
         Ok(int) => Ok((Token::Num(int), i)),
         Err(e) => Err(MyError::LexerIntError(err_str, e)),
@@ -112,9 +223,14 @@ fn tok_num_int(chars: &[char], index: usize) -> Result<(Token, usize), MyError>
 
 /// Eat numbers from input array.
 /// Foreword to `tok_num_int()` when integer, i.e. decimal, hexadecimal, octal or binary.
+/// Leading '0' means octal (C style) if `leading_zero_octal`, otherwise decimal.
 /// Return `Token::Num()` or `Token::FNum()` with `Result<,Err(String)>`.
 /// Increment index and return as a member of tuple.
-fn tok_num(chars: &[char], index: usize) -> Result<(Token, usize), MyError> {
+fn tok_num(
+    chars: &[char],
+    index: usize,
+    leading_zero_octal: bool,
+) -> Result<(Token, usize), MyError> {
     let mut i = index;
     let mut mantissa = String::new();
     let mut exponent = String::new();
@@ -124,7 +240,8 @@ fn tok_num(chars: &[char], index: usize) -> Result<(Token, usize), MyError> {
         if (i + 1) < chars.len() {
             i += 1;
             match chars[i] {
-                '0'..='9' | 'a'..='f' | 'A'..='F' | 'x' | 'X' => {
+                '0'..='9' if !leading_zero_octal => {}
+                '0'..='9' | 'a'..='f' | 'A'..='F' | 'x' | 'X' | 'o' | 'O' => {
                     return tok_num_int(chars, i);
                 }
                 '.' => {
@@ -167,6 +284,19 @@ fn tok_num(chars: &[char], index: usize) -> Result<(Token, usize), MyError> {
                     break;
                 }
             }
+            '#' => {
+                // 36#ZZ : explicit radix. '#' not followed by a digit is a comment.
+                if let Ok(radix) = mantissa.parse::<u32>() {
+                    if (2..=36).contains(&radix)
+                        && !has_dot
+                        && i + 1 < chars.len()
+                        && chars[i + 1].is_digit(radix)
+                    {
+                        return tok_num_radix(chars, i + 1, radix);
+                    }
+                }
+                break;
+            }
             _ => {
                 break;
             }
@@ -231,8 +361,25 @@ fn tok_ident(chars: &[char], index: usize) -> (Token, usize) {
 /// assert_eq!(lexer("1e3".to_owned()).unwrap(), [Token::FNum(1000.0)]);
 /// assert_eq!(lexer("9223372036854775807".to_owned()).unwrap(), [Token::Num(9223372036854775807)]);
 /// assert_eq!(lexer("18446744073709551615".to_owned()).unwrap(), [Token::Num(18446744073709551615)]);
+/// assert_eq!(lexer("0o11".to_owned()).unwrap(), [Token::Num(9)]);
+/// assert_eq!(lexer("36#zz".to_owned()).unwrap(), [Token::Num(1295)]);
+/// assert_eq!(lexer("0x1.8p3".to_owned()).unwrap(), [Token::FNum(12.0)]);
 /// ```
 pub fn lexer(s: String) -> Result<Vec<Token>, MyError> {
+    lexer_with(s, true)
+}
+
+/// Same as `lexer()` but leading zero is decimal (`011` is 11) unless `leading_zero_octal`.
+///
+/// # Examples
+/// ```
+/// use rc::lexer_with;
+/// use rc::Token;
+/// assert_eq!(lexer_with("011".to_owned(), true).unwrap(), [Token::Num(9)]);
+/// assert_eq!(lexer_with("011".to_owned(), false).unwrap(), [Token::Num(11)]);
+/// assert_eq!(lexer_with("0o11".to_owned(), false).unwrap(), [Token::Num(9)]);
+/// ```
+pub fn lexer_with(s: String, leading_zero_octal: bool) -> Result<Vec<Token>, MyError> {
     let mut ret = Vec::new();
 
     let chars: Vec<char> = s.chars().collect();
@@ -241,7 +388,7 @@ pub fn lexer(s: String) -> Result<Vec<Token>, MyError> {
         match chars[i] {
             '0'..='9' => {
                 // `Num` or `FNum` begin from '0'..='9'.
-                let (tk, j) = tok_num(&chars, i)?;
+                let (tk, j) = tok_num(&chars, i, leading_zero_octal)?;
                 i = j;
                 ret.push(tk);
             }
@@ -298,6 +445,18 @@ pub fn lexer(s: String) -> Result<Vec<Token>, MyError> {
                 ret.push(Token::Op(TokenOp::Equal));
                 i += 1;
             }
+            '∠' => {
+                ret.push(Token::Op(TokenOp::Angle));
+                i += 1;
+            }
+            '{' => {
+                ret.push(Token::Op(TokenOp::BraceLeft));
+                i += 1;
+            }
+            '}' => {
+                ret.push(Token::Op(TokenOp::BraceRight));
+                i += 1;
+            }
             'a'..='z' | 'A'..='Z' | '_' => {
                 let (tk, j) = tok_ident(&chars, i);
                 i = j;
@@ -350,40 +509,73 @@ mod tests {
     }
     #[test]
     fn test_tok_num() {
-        assert_tok_index(tok_num(&s2v("0x1"), 0), Token::Num(1), 3);
-        assert_tok_index(tok_num(&s2v("0xa"), 0), Token::Num(10), 3);
-        assert_tok_index(tok_num(&s2v("0x10"), 0), Token::Num(16), 4);
-        assert_tok_index(tok_num(&s2v("0b10"), 0), Token::Num(2), 4);
-        assert_tok_index(tok_num(&s2v("0b1_0"), 0), Token::Num(2), 5);
-        assert_tok_index(tok_num(&s2v("010"), 0), Token::Num(8), 3);
-        assert_tok_index(tok_num(&s2v("10"), 0), Token::Num(10), 2);
-        assert_tok_index(tok_num(&s2v("10.1"), 0), Token::FNum(10.1), 4);
-        assert_tok_index(tok_num(&s2v("10e1"), 0), Token::FNum(100.0), 4);
-        assert_tok_index(tok_num(&s2v("10e1+"), 0), Token::FNum(100.0), 4);
-        assert_tok_index(tok_num(&s2v("1"), 0), Token::Num(1), 1);
-        assert_tok_index(tok_num(&s2v("0"), 0), Token::Num(0), 1);
-        assert_tok_index(tok_num(&s2v("10"), 0), Token::Num(10), 2);
-        assert_tok_index(tok_num(&s2v("1.1"), 0), Token::FNum(1.1), 3);
-        assert_tok_index(tok_num(&s2v("0.1"), 0), Token::FNum(0.1), 3);
-        assert_tok_index(tok_num(&s2v("1.1E2"), 0), Token::FNum(110.0), 5);
-        assert_tok_index(tok_num(&s2v("1.1E-2"), 0), Token::FNum(0.011), 6);
-        assert_tok_index(tok_num(&s2v("100_000"), 0), Token::Num(100000), 7);
-        assert_tok_index(tok_num(&s2v("0xa"), 0), Token::Num(10), 3);
-        assert_tok_index(tok_num(&s2v("011"), 0), Token::Num(9), 3);
-        assert_tok_index(tok_num(&s2v("0b11"), 0), Token::Num(3), 4);
-        assert_tok_index(tok_num(&s2v("1e3"), 1), Token::FNum(1000.0), 3);
+        assert_tok_index(tok_num(&s2v("0x1"), 0, true), Token::Num(1), 3);
+        assert_tok_index(tok_num(&s2v("0xa"), 0, true), Token::Num(10), 3);
+        assert_tok_index(tok_num(&s2v("0x10"), 0, true), Token::Num(16), 4);
+        assert_tok_index(tok_num(&s2v("0b10"), 0, true), Token::Num(2), 4);
+        assert_tok_index(tok_num(&s2v("0b1_0"), 0, true), Token::Num(2), 5);
+        assert_tok_index(tok_num(&s2v("010"), 0, true), Token::Num(8), 3);
+        assert_tok_index(tok_num(&s2v("10"), 0, true), Token::Num(10), 2);
+        assert_tok_index(tok_num(&s2v("10.1"), 0, true), Token::FNum(10.1), 4);
+        assert_tok_index(tok_num(&s2v("10e1"), 0, true), Token::FNum(100.0), 4);
+        assert_tok_index(tok_num(&s2v("10e1+"), 0, true), Token::FNum(100.0), 4);
+        assert_tok_index(tok_num(&s2v("1"), 0, true), Token::Num(1), 1);
+        assert_tok_index(tok_num(&s2v("0"), 0, true), Token::Num(0), 1);
+        assert_tok_index(tok_num(&s2v("10"), 0, true), Token::Num(10), 2);
+        assert_tok_index(tok_num(&s2v("1.1"), 0, true), Token::FNum(1.1), 3);
+        assert_tok_index(tok_num(&s2v("0.1"), 0, true), Token::FNum(0.1), 3);
+        assert_tok_index(tok_num(&s2v("1.1E2"), 0, true), Token::FNum(110.0), 5);
+        assert_tok_index(tok_num(&s2v("1.1E-2"), 0, true), Token::FNum(0.011), 6);
+        assert_tok_index(tok_num(&s2v("100_000"), 0, true), Token::Num(100000), 7);
+        assert_tok_index(tok_num(&s2v("0xa"), 0, true), Token::Num(10), 3);
+        assert_tok_index(tok_num(&s2v("011"), 0, true), Token::Num(9), 3);
+        assert_tok_index(tok_num(&s2v("0b11"), 0, true), Token::Num(3), 4);
+        assert_tok_index(tok_num(&s2v("1e3"), 1, true), Token::FNum(1000.0), 3);
         assert_tok_index(
-            tok_num(&s2v("9223372036854775807"), 0),
+            tok_num(&s2v("9223372036854775807"), 0, true),
             Token::Num(9223372036854775807),
             19,
         );
         assert_tok_index(
-            tok_num(&s2v("18446744073709551615"), 0),
+            tok_num(&s2v("18446744073709551615"), 0, true),
             Token::Num(18446744073709551615),
             20,
         );
     }
 
+    #[test]
+    fn test_tok_num_radix() {
+        assert_tok_index(tok_num(&s2v("0o17"), 0, true), Token::Num(15), 4);
+        assert_tok_index(tok_num(&s2v("0O17"), 0, false), Token::Num(15), 4);
+        assert_tok_index(tok_num(&s2v("011"), 0, false), Token::Num(11), 3);
+        assert_tok_index(tok_num(&s2v("0.5"), 0, false), Token::FNum(0.5), 3);
+        assert_tok_index(tok_num(&s2v("36#ZZ"), 0, true), Token::Num(1295), 5);
+        assert_tok_index(tok_num(&s2v("2#1_01+"), 0, true), Token::Num(5), 6);
+        assert_tok_index(tok_num(&s2v("16#ff"), 0, true), Token::Num(255), 5);
+        assert_tok_index(tok_num(&s2v("10#comment"), 0, true), Token::Num(10), 2);
+        assert_eq!(lexer("1 #comment".to_owned()).unwrap(), [Token::Num(1)]);
+        assert_eq!(lexer("8#1#comment".to_owned()).unwrap(), [Token::Num(1)]);
+    }
+
+    #[test]
+    fn test_tok_hex_float() {
+        assert_tok_index(tok_num(&s2v("0x1.8p3"), 0, true), Token::FNum(12.0), 7);
+        assert_tok_index(tok_num(&s2v("0x1p-2"), 0, true), Token::FNum(0.25), 6);
+        assert_tok_index(tok_num(&s2v("0xA.8P+1"), 0, true), Token::FNum(21.0), 8);
+        assert_tok_index(tok_num(&s2v("0x.8"), 0, true), Token::FNum(0.5), 4);
+        assert_tok_index(
+            tok_num(&s2v("0x1.fffffffffffffp1023"), 0, true),
+            Token::FNum(f64::MAX),
+            22,
+        );
+        // pico postfix
+        assert_eq!(
+            lexer("0x10p".to_owned()).unwrap(),
+            [Token::Num(16), Token::Ident("p".to_owned())]
+        );
+        assert_tok_index(tok_num(&s2v("0x1.8p"), 0, true), Token::FNum(1.5), 5);
+    }
+
     #[test]
     fn test_tok_num_error() {
         if lexer("018".to_owned()).is_ok() {
@@ -392,6 +584,12 @@ mod tests {
         if lexer("0b12".to_owned()).is_ok() {
             panic!("lexer(\"0b12\") should return error.");
         }
+        if lexer("0o8".to_owned()).is_ok() {
+            panic!("lexer(\"0o8\") should return error.");
+        }
+        if lexer("2#12".to_owned()).is_ok() {
+            panic!("lexer(\"2#12\") should return error.");
+        }
     }
 
     #[test]
@@ -495,6 +693,25 @@ mod tests {
                 Token::Num(1),
             ]
         );
+        assert_eq!(
+            lexer("10∠-45".to_owned()).unwrap(),
+            [
+                Token::Num(10),
+                Token::Op(TokenOp::Angle),
+                Token::Op(TokenOp::Minus),
+                Token::Num(45),
+            ]
+        );
+        assert_eq!(
+            lexer("{1, 2}".to_owned()).unwrap(),
+            [
+                Token::Op(TokenOp::BraceLeft),
+                Token::Num(1),
+                Token::Op(TokenOp::Comma),
+                Token::Num(2),
+                Token::Op(TokenOp::BraceRight),
+            ]
+        );
         assert_eq!(
             lexer("10[mm/s]".to_owned()).unwrap(),
             [
@@ -6,18 +6,26 @@ use thiserror::Error;
 
 mod env;
 mod lexer;
+mod matrix;
+mod numeric;
 mod parser;
 mod readline;
 mod run_test;
 mod script;
+mod symbolic;
+mod transfer;
 mod units;
 
 pub use env::*;
 pub use lexer::*;
+pub use matrix::*;
+pub use numeric::*;
 pub use parser::*;
 pub use readline::readline;
 pub use run_test::run_test;
 pub use script::*;
+pub use symbolic::*;
+pub use transfer::*;
 pub use units::*;
 
 #[derive(Error, Debug)]
@@ -192,6 +200,17 @@ fn eval_parallel(env: &Env, lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
     ))
 }
 
+// magnitude∠angle, angle is in the current angle mode.
+fn eval_polar(env: &Env, lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
+    let magnitude = eval_fvalue(env, lhs)?;
+    let angle = eval_fvalue(env, rhs)? * env.angle_mode.radian();
+    let units = match lhs {
+        Node::Num(_, units) | Node::FNum(_, units) => units.clone(),
+        _ => Box::new(Node::Units(Box::new(Node::None))),
+    };
+    Ok(Node::CNum(Complex64::from_polar(magnitude, angle), units))
+}
+
 fn eval_modulo(lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
     if let (Node::Num(nl, _), Node::Num(nr, units)) = (lhs, rhs) {
         Ok(Node::Num(nl % nr, units.clone()))
@@ -269,6 +288,34 @@ fn eval_const(env: &Env, n: &Node) -> Result<Node, MyError> {
     )))
 }
 
+// Mark the tokens of the lambda of map/filter in a user function body,
+// `_1` of `defun f map(_1*_2, _1)` is the element, `_2` is the parameter.
+fn lambda_tokens(tokens: &[Token]) -> Vec<bool> {
+    let mut lambda = vec![false; tokens.len()];
+    for i in 0..tokens.len() {
+        let is_lambda_func = matches!(&tokens[i], Token::Ident(id) if id == "map" || id == "filter");
+        if !is_lambda_func || tokens.get(i + 1) != Some(&Token::Op(TokenOp::ParenLeft)) {
+            continue;
+        }
+        let mut depth = 0;
+        for j in i + 2..tokens.len() {
+            match tokens[j] {
+                Token::Op(TokenOp::ParenLeft) | Token::Op(TokenOp::BraceLeft) => depth += 1,
+                Token::Op(TokenOp::ParenRight) | Token::Op(TokenOp::BraceRight) => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                }
+                Token::Op(TokenOp::Comma) if depth == 0 => break,
+                _ => {}
+            }
+            lambda[j] = true;
+        }
+    }
+    lambda
+}
+
 fn node_to_token(n: Node) -> Vec<Token> {
     match n {
         Node::Num(n, _) => vec![Token::Num(n)],
@@ -282,22 +329,103 @@ fn node_to_token(n: Node) -> Vec<Token> {
             Token::Ident("i".to_owned()),
             Token::Op(TokenOp::ParenRight),
         ],
+        Node::List(items) => {
+            let mut tokens = vec![Token::Op(TokenOp::BraceLeft)];
+            for (index, item) in items.into_iter().enumerate() {
+                if index > 0 {
+                    tokens.push(Token::Op(TokenOp::Comma));
+                }
+                tokens.append(&mut node_to_token(item));
+            }
+            tokens.push(Token::Op(TokenOp::BraceRight));
+            tokens
+        }
+        Node::Matrix(rows) => {
+            node_to_token(Node::List(rows.into_iter().map(Node::List).collect()))
+        }
+        Node::TransferFn(num, den) => {
+            let mut tokens = vec![
+                Token::Ident("tf".to_owned()),
+                Token::Op(TokenOp::ParenLeft),
+            ];
+            tokens.append(&mut node_to_token(Node::List(num)));
+            tokens.push(Token::Op(TokenOp::Comma));
+            tokens.append(&mut node_to_token(Node::List(den)));
+            tokens.push(Token::Op(TokenOp::ParenRight));
+            tokens
+        }
         _ => Vec::new(),
     }
 }
 
+// Apply a function to each element of list arguments, i.e. sin({0, pi/2}).
+// Functions in `env.list_func` such as max() take the list itself.
+fn eval_list_func(env: &mut Env, ident: &str, params: &[Node]) -> Result<Node, MyError> {
+    let mut len = None;
+    for p in params {
+        if let Node::List(items) = p {
+            if len.is_some() && len != Some(items.len()) {
+                return Err(MyError::EvalError(format!(
+                    "list length mismatch: {}()",
+                    ident
+                )));
+            }
+            len = Some(items.len());
+        }
+    }
+    let mut values = Vec::new();
+    for index in 0..len.unwrap_or(0) {
+        let args = params
+            .iter()
+            .map(|p| match p {
+                Node::List(items) => items[index].clone(),
+                _ => p.clone(),
+            })
+            .collect();
+        values.push(eval_func(env, &Node::Func(Token::Ident(ident.to_owned()), args))?);
+    }
+    Ok(Node::List(values))
+}
+
 fn eval_func(env: &mut Env, n: &Node) -> Result<Node, MyError> {
     if env.is_debug() {
         eprintln!("eval_func {:?}\r", n);
     }
     if let Node::Func(Token::Ident(ident), param) = n {
+        if let Some(func_tuple) = env.is_lazy_func(ident.as_str()) {
+            if func_tuple.1 != 0 && func_tuple.1 != param.len() {
+                return Err(MyError::EvalError(format!(
+                    "{} requires {} parameter(s): {:?}",
+                    ident, func_tuple.1, n
+                )));
+            }
+            let new_node = func_tuple.0(env, param)?;
+            return do_eval(env, &new_node);
+        }
         if let Some(func_tuple) = env.is_func(ident.as_str()) {
+            if func_tuple.1 != 0 && func_tuple.1 != param.len() {
+                return Err(MyError::EvalError(format!(
+                    "{} requires {} parameter(s): {:?}",
+                    ident, func_tuple.1, n
+                )));
+            }
             let mut params: Vec<Node> = Vec::new();
             for i in param {
                 let param_value = eval(env, i)?;
                 params.push(param_value);
             }
+            if !env.list_func.contains(ident.as_str())
+                && params.iter().any(|p| matches!(p, Node::List(_)))
+            {
+                return eval_list_func(env, ident, &params);
+            }
             let new_node = func_tuple.0(env, &params);
+            if let Node::None = new_node {
+                return Err(MyError::EvalError(format!(
+                    "invalid argument of {}(): {:?}",
+                    ident, param
+                )));
+            }
             return do_eval(env, &new_node);
         }
         if let Some(tokens) = env.is_user_func((*ident).clone()) {
@@ -306,9 +434,13 @@ fn eval_func(env: &mut Env, n: &Node) -> Result<Node, MyError> {
                 let param_value = eval(env, i)?;
                 params.push(param_value);
             }
+            let lambda = lambda_tokens(&tokens);
             let mut new_tokens: Vec<Token> = Vec::new();
-            for t in tokens {
+            for (t, in_lambda) in tokens.into_iter().zip(lambda) {
                 match t {
+                    Token::Ident(ref id) if in_lambda && id == "_1" => {
+                        new_tokens.push(t);
+                    }
                     Token::Ident(ref id) => {
                         if id == "_1" {
                             if params.is_empty() {
@@ -498,7 +630,16 @@ fn eval_binop(env: &mut Env, n: &Node) -> Result<Node, MyError> {
         }
         let lhs = do_eval(env, lhs)?;
         let rhs = do_eval(env, rhs)?;
-        
+        if let (Node::TransferFn(_, _), _) | (_, Node::TransferFn(_, _)) = (&lhs, &rhs) {
+            return eval_tf_binop(env, tok, &lhs, &rhs);
+        }
+        if let (Node::Matrix(_), _) | (_, Node::Matrix(_)) = (&lhs, &rhs) {
+            return eval_matrix_binop(env, tok, &lhs, &rhs);
+        }
+        if let (Node::List(_), _) | (_, Node::List(_)) = (&lhs, &rhs) {
+            return eval_list_binop(env, tok, &lhs, &rhs);
+        }
+
         match tok {
             Token::Op(TokenOp::Plus) => eval_add(env, &lhs, &rhs),
             Token::Op(TokenOp::Minus) => eval_subtract(env, &lhs, &rhs),
@@ -507,6 +648,7 @@ fn eval_binop(env: &mut Env, n: &Node) -> Result<Node, MyError> {
             Token::Op(TokenOp::Para) => eval_parallel(env, &lhs, &rhs),
             Token::Op(TokenOp::Mod) => eval_modulo(&lhs, &rhs),
             Token::Op(TokenOp::Caret) => eval_power(&lhs, &rhs),
+            Token::Op(TokenOp::Angle) => eval_polar(env, &lhs, &rhs),
             _ => Err(MyError::EvalError(format!(
                 "unknown binary operator: {:?}",
                 n
@@ -517,6 +659,30 @@ fn eval_binop(env: &mut Env, n: &Node) -> Result<Node, MyError> {
     }
 }
 
+// Element-wise operation of lists, a scalar operand is applied to each element.
+fn eval_list_binop(env: &mut Env, tok: &Token, lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
+    let pairs: Vec<(Node, Node)> = match (lhs, rhs) {
+        (Node::List(l), Node::List(r)) => {
+            if l.len() != r.len() {
+                return Err(MyError::EvalError(format!(
+                    "list length mismatch: {} and {}",
+                    l.len(),
+                    r.len()
+                )));
+            }
+            l.iter().cloned().zip(r.iter().cloned()).collect()
+        }
+        (Node::List(l), _) => l.iter().map(|a| (a.clone(), rhs.clone())).collect(),
+        (_, Node::List(r)) => r.iter().map(|b| (lhs.clone(), b.clone())).collect(),
+        (_, _) => return Err(MyError::EvalError("list operator without list".to_owned())),
+    };
+    let mut values = Vec::new();
+    for (a, b) in pairs {
+        values.push(eval_binop(env, &Node::BinOp(tok.clone(), Box::new(a), Box::new(b)))?);
+    }
+    Ok(Node::List(values))
+}
+
 fn eval_unary(env: &mut Env, n: &Node) -> Result<Node, MyError> {
     if env.is_debug() {
         eprintln!("eval_unary {:?}\r", n);
@@ -533,6 +699,18 @@ fn eval_unary(env: &mut Env, n: &Node) -> Result<Node, MyError> {
                 return Ok(Node::FNum(-f, units));
             } else if let Node::CNum(c, units) = para {
                 return Ok(Node::CNum(-c, units));
+            } else if let Node::List(items) = para {
+                let mut values = Vec::new();
+                for item in items {
+                    values.push(eval_unary(env, &Node::Unary(tok.clone(), Box::new(item)))?);
+                }
+                return Ok(Node::List(values));
+            } else if let Node::TransferFn(_, _) = para {
+                let minus_one = Node::Num(-1, Box::new(Node::Units(Box::new(Node::None))));
+                return eval_tf_binop(env, &Token::Op(TokenOp::Mul), &minus_one, &para);
+            } else if let Node::Matrix(_) = para {
+                let minus_one = Node::Num(-1, Box::new(Node::Units(Box::new(Node::None))));
+                return eval_matrix_binop(env, &Token::Op(TokenOp::Mul), &minus_one, &para);
             } else {
                 let result = eval(env, &para)?;
                 let new_node = Node::Unary(Token::Op(TokenOp::Minus), Box::new(result));
@@ -557,18 +735,59 @@ fn do_eval(env: &mut Env, n: &Node) -> Result<Node, MyError> {
         Node::Func(_tok, _params) => eval_func(env, n),
         Node::Command(_tok, _params, _result) => eval_command(env, n),
         Node::None => Err(MyError::EvalError(format!("invalid node {:?}", n))),
+        Node::List(items) => {
+            let mut values = Vec::new();
+            for item in items {
+                values.push(do_eval(env, item)?);
+            }
+            Ok(Node::List(values))
+        }
+        Node::Matrix(rows) => {
+            let mut values = Vec::new();
+            for row in rows {
+                let mut row_values = Vec::new();
+                for entry in row {
+                    match do_eval(env, entry)? {
+                        value @ (Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _)) => {
+                            row_values.push(value)
+                        }
+                        value => {
+                            return Err(MyError::EvalError(format!(
+                                "matrix entry should be a number: {:?}",
+                                value
+                            )))
+                        }
+                    }
+                }
+                values.push(row_values);
+            }
+            Ok(Node::Matrix(values))
+        }
+        Node::TransferFn(_, _) => Ok(n.clone()),
         Node::Units(_) => todo!(),
         Node::UnitsFraction(_, _) => todo!(),
     }
 }
 
-fn eval(env: &mut Env, n: &Node) -> Result<Node, MyError> {
-    if env.is_debug() {
-        eprintln!("eval {:?}\r", n);
+// Reduce units of the value to `UnitsFraction`. Each element of a list or a matrix is reduced.
+fn eval_units_final(env: &mut Env, ret: Node) -> Node {
+    if let Node::Matrix(rows) = ret {
+        return Node::Matrix(
+            rows.into_iter()
+                .map(|row| row.into_iter().map(|entry| eval_units_final(env, entry)).collect())
+                .collect(),
+        );
+    }
+    if let Node::List(items) = ret {
+        return Node::List(
+            items
+                .into_iter()
+                .map(|item| eval_units_final(env, item))
+                .collect(),
+        );
     }
-    let ret = do_eval(env, n)?;
     let ret = eval_units_reduce(env, ret);
-    let ret = match ret {
+    match ret {
         Node::Num(n, ref u) => {
             if let Node::Units(units_content) = &**u {
                 let u = eval_units_fraction(env, (**units_content).clone());
@@ -594,22 +813,54 @@ fn eval(env: &mut Env, n: &Node) -> Result<Node, MyError> {
             }
         }
         _ => ret,
-    };
+    }
+}
+
+fn eval(env: &mut Env, n: &Node) -> Result<Node, MyError> {
+    if env.is_debug() {
+        eprintln!("eval {:?}\r", n);
+    }
+    let ret = do_eval(env, n)?;
+    let ret = eval_units_final(env, ret);
     match ret {
         Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _) => Ok(ret),
+        Node::List(_) | Node::Matrix(_) | Node::TransferFn(_, _) => Ok(ret),
         Node::Command(_, _, _) => Ok(ret),
         Node::None => Ok(ret),
         _ => eval(env, &ret),
     }
 }
 
+// Evaluate `n` with a temporary value of the variable, i.e. `x` of solve(x^2-2, x, 1).
+fn eval_with_variable(env: &mut Env, name: &str, value: Node, n: &Node) -> Result<Node, MyError> {
+    let saved = env.variable.insert(name.to_owned(), value);
+    let ret = eval(env, n);
+    match saved {
+        Some(v) => env.variable.insert(name.to_owned(), v),
+        None => env.variable.remove(name),
+    };
+    ret
+}
+
+// Apply `f` to the value. `f` is a name of function, i.e. map(sqrt, v),
+// or an expression of `_1` (lambda), i.e. map(_1*2, v).
+fn apply_lambda(env: &mut Env, f: &Node, value: Node) -> Result<Node, MyError> {
+    if let Node::Var(Token::Ident(id)) = f {
+        if env.is_func(id).is_some() || env.is_user_func(id.clone()).is_some() {
+            return eval(env, &Node::Func(Token::Ident(id.clone()), vec![value]));
+        }
+    }
+    eval_with_variable(env, "_1", value, f)
+}
+
 pub fn eval_top(env: &mut Env, n: &Node) -> Result<Node, MyError> {
     if env.is_debug() {
         eprintln!("eval {:?}\r", n);
     }
     let result = eval(env, n)?;
     match result {
-        Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _) => {
+        Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _)
+        | Node::List(_) | Node::Matrix(_) | Node::TransferFn(_, _) => {
             env.set_variable("ans".to_owned(), result.clone())?;
             Ok(result)
         }
@@ -840,6 +1091,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_polar_complex() {
+        let mut env = Env::new();
+        env.built_in();
+
+        let c = eval_as_complex64(&mut env, "2∠(pi/2)");
+        assert!(c.re.abs() < 1e-10);
+        assert!((c.im - 2.0).abs() < 1e-10);
+        let c = eval_as_complex64(&mut env, "polar(2, -pi)");
+        assert!((c.re + 2.0).abs() < 1e-10);
+        assert!(c.im.abs() < 1e-10);
+        let c = eval_as_complex64(&mut env, "10∠45[deg]");
+        assert!((c.re - 50.0_f64.sqrt()).abs() < 1e-10);
+        assert!((c.im - 50.0_f64.sqrt()).abs() < 1e-10);
+
+        eval_as_string(&mut env, "angle deg");
+        let c = eval_as_complex64(&mut env, "1+10∠-90");
+        assert!((c.re - 1.0).abs() < 1e-10);
+        assert!((c.im + 10.0).abs() < 1e-10);
+
+        env.complex_format = ComplexFormat::Polar;
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(0.0, 2.0)),
+            "2∠90"
+        );
+        env.float_format = FloatFormat::Eng;
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(-1500.0, 0.0)),
+            "1.5k∠180"
+        );
+        env.float_format = FloatFormat::Fix;
+        eval_as_string(&mut env, "format decimals 2");
+        assert_eq!(
+            output_format_complex(&mut env, Complex64::new(1.0, 2.0)),
+            "2.24∠63.43"
+        );
+    }
+
     #[test]
     fn test_famous_complex_identities() {
         let mut env = Env::new();
@@ -891,6 +1180,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_log_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert!((eval_as_f64(&mut env, "ln(e)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "log10(1000)") - 3.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "log2(1024)") - 10.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "log(81, 3)") - 4.0).abs() < 1e-10);
+
+        // Complex logarithm: ln(-1) = i*pi
+        assert!((eval_as_complex64(&mut env, "ln(-1)").re).abs() < 1e-10);
+        assert!((eval_as_complex64(&mut env, "ln(-1)").im - std::f64::consts::PI).abs() < 1e-10);
+        assert!((eval_as_complex64(&mut env, "log10(10i)").re - 1.0).abs() < 1e-10);
+
+        // Decibel
+        assert!((eval_as_f64(&mut env, "pow2db(100)") - 20.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "mag2db(100)") - 40.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "db2mag(-6)") - 0.501187233627272).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "db2pow(pow2db(2.5))") - 2.5).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "w2dbm(1)") - 30.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "dbm2w(0)") - 0.001).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "w2dbw(0.1)") + 10.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "dbw2w(20)") - 100.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "v2dbv(10)") - 20.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "dbv2v(-20)") - 0.1).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "v2dbu(0.7745966692414834)")).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "dbu2v(v2dbu(1.23))") - 1.23).abs() < 1e-10);
+
+        // Neper
+        assert!((eval_as_f64(&mut env, "np2db(1)") - 8.685889638065035).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "db2np(np2db(2))") - 2.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "mag2np(e)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "np2mag(1)") - std::f64::consts::E).abs() < 1e-10);
+
+        // out of the domain
+        assert!((eval_as_f64(&mut env, "db2pow(-3)") - 0.501187233627272).abs() < 1e-10);
+        for input in ["log(8, 1)", "log(8, 0)", "pow2db(-1)", "mag2db(-2)", "w2dbm(-1)"] {
+            let n = parse(&mut env, &(lexer(input.to_owned())).unwrap()).unwrap();
+            assert!(eval(&mut env, &n).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_trigonometric_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert!((eval_as_f64(&mut env, "tan(pi/4)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "asin(1)") - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "acos(-1)") - std::f64::consts::PI).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "atan(1)") - std::f64::consts::FRAC_PI_4).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "atan2(1, -1)") - 3.0 * std::f64::consts::FRAC_PI_4).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "sinh(1)") - 1.1752011936438014).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "cosh(0)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "tanh(asinh(0.5))") - 0.5 / 1.25_f64.sqrt()).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "acosh(cosh(2))") - 2.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "atanh(tanh(0.3))") - 0.3).abs() < 1e-10);
+
+        // Outside of the real domain, the result is complex
+        let c = eval_as_complex64(&mut env, "asin(2)");
+        assert!((c.re - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+        assert!((c.im.abs() - 1.3169578969248166).abs() < 1e-10);
+        let c = eval_as_complex64(&mut env, "acosh(0)");
+        assert!((c.im - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+        let c = eval_as_complex64(&mut env, "tan(i)");
+        assert!((c.im - 1.0_f64.tanh()).abs() < 1e-10);
+        let c = eval_as_complex64(&mut env, "sin(asin(1+2i))");
+        assert!((c.re - 1.0).abs() < 1e-10);
+        assert!((c.im - 2.0).abs() < 1e-10);
+
+        // Argument count
+        let n = Node::Func(
+            Token::Ident("atan2".to_owned()),
+            vec![Node::Num(1, Box::new(Node::Units(Box::new(Node::None))))],
+        );
+        assert!(eval(&mut env, &n).is_err());
+    }
+
+    #[test]
+    fn test_angle_mode() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert!((eval_as_f64(&mut env, "deg(pi)") - 180.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "rad(90)") - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "sin(90[deg])") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "100[grad]") - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+
+        eval_as_string(&mut env, "angle deg");
+        assert!((eval_as_f64(&mut env, "sin(30)") - 0.5).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "cos(60)") - 0.5).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "tan(45)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "asin(1)") - 90.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "acos(0)") - 90.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "atan(1)") - 45.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "atan2(-1, 0)") + 90.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "arg(1+i)") - 45.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "sin(1.5707963267948966[rad])") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "90[deg]") - 90.0).abs() < 1e-10);
+
+        eval_as_string(&mut env, "angle grad");
+        assert!((eval_as_f64(&mut env, "sin(100)") - 1.0).abs() < 1e-10);
+        assert!((eval_as_f64(&mut env, "asin(1)") - 100.0).abs() < 1e-10);
+
+        eval_as_string(&mut env, "angle rad");
+        assert!((eval_as_f64(&mut env, "asin(1)") - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+
+        // a function name is a unit only in `[...]`
+        assert!((eval_as_f64(&mut env, "2[deg]*deg(1)") - 2.0).abs() < 1e-10);
+        assert!(parse(&mut env, &(lexer("deg + 1".to_owned())).unwrap()).is_err());
+        assert!(parse(&mut env, &(lexer("[deg".to_owned())).unwrap()).is_err());
+        assert!(parse(&mut env, &(lexer("sin".to_owned())).unwrap()).is_err());
+    }
+
     #[test]
     fn test_user_defined_functions() {
         let mut env = Env::new();
@@ -917,6 +1321,444 @@ mod tests {
         assert_eq!(eval_as_string(&mut env, "plus_a(8)"), "Num(13, []/[])".to_owned());
     }
 
+    #[test]
+    fn test_ieee_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "f64bits(1.5)"), "Num(4609434218613702656, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "f32bits(1.5)"), "Num(1069547520, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "fsign(-2.0)"), "Num(1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "fexp(1.5)"), "Num(1023, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "fmant(1.5)"), "Num(2251799813685248, []/[])".to_owned());
+        assert_eq!(eval_as_f64(&mut env, "bits2f64(0x3ff8000000000000)"), 1.5);
+        assert_eq!(eval_as_f64(&mut env, "bits2f32(0x3fc00000)"), 1.5);
+        assert_eq!(eval_as_f64(&mut env, "bits2f64(f64bits(0.1))"), 0.1);
+        assert_eq!(eval_as_f64(&mut env, "0x1.8p3"), 12.0);
+    }
+
+    #[test]
+    fn test_q_format() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "q(0.5, 1, 15)"), "Num(16384, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "q(-0.5, 1, 15)"), "Num(-16384, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "q(1.0, 1, 15)"), "Num(32767, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "q(-2.0, 1, 15)"), "Num(-32768, []/[])".to_owned());
+        assert_eq!(eval_as_f64(&mut env, "fromq(0xc000, 1, 15)"), -0.5);
+        assert_eq!(eval_as_f64(&mut env, "fromq(-16384, 1, 15)"), -0.5);
+        assert_eq!(eval_as_f64(&mut env, "fromq(q(3.25, 4, 4), 4, 4)"), 3.25);
+        assert!((eval_as_f64(&mut env, "qerr(0.1, 1, 7)") - (13.0 / 128.0 - 0.1)).abs() < 1e-15);
+
+        // Rounding and overflow modes
+        assert_eq!(eval_as_string(&mut env, "q(2.5, 4, 0)"), "Num(3, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "q(-2.5, 4, 0)"), "Num(-3, []/[])".to_owned());
+        eval_as_string(&mut env, "qmode even");
+        assert_eq!(eval_as_string(&mut env, "q(2.5, 4, 0)"), "Num(2, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "q(3.5, 4, 0)"), "Num(4, []/[])".to_owned());
+        eval_as_string(&mut env, "qmode floor");
+        assert_eq!(eval_as_string(&mut env, "q(-2.5, 4, 0)"), "Num(-3, []/[])".to_owned());
+        eval_as_string(&mut env, "qmode trunc");
+        assert_eq!(eval_as_string(&mut env, "q(-2.5, 4, 0)"), "Num(-2, []/[])".to_owned());
+        eval_as_string(&mut env, "qmode round wrap");
+        assert_eq!(eval_as_string(&mut env, "q(1.0, 1, 15)"), "Num(-32768, []/[])".to_owned());
+    }
+
+    #[test]
+    fn test_bit_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "bits(0x8305, 15, 8)"), "Num(131, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bits(-1, 3, 0)"), "Num(15, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "insbits(0x8305, 3, 1, 5)"), "Num(33547, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bset(0, 4)"), "Num(16, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bclr(7, 1)"), "Num(5, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "btest(4, 2)"), "Num(1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "btest(4, 1)"), "Num(0, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "popcount(0xff00)"), "Num(8, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "popcount(-1, 16)"), "Num(16, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "clz(1)"), "Num(31, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "clz(1, 8)"), "Num(7, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "ctz(8)"), "Num(3, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "ctz(0, 16)"), "Num(16, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bswap(0x12345678)"), "Num(2018915346, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bswap(0x1234, 16)"), "Num(13330, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "bitrev(1, 8)"), "Num(128, []/[])".to_owned());
+        eval_as_string(&mut env, "format word 16");
+        assert_eq!(eval_as_string(&mut env, "clz(1)"), "Num(15, []/[])".to_owned());
+
+        let n = parse(&mut env, &(lexer("bswap(1, 12)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "bswap width should be multiple of 8");
+        let n = parse(&mut env, &(lexer("bits(1, 0, 3)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "msb should not be less than lsb");
+    }
+
+    #[test]
+    fn test_number_theory() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "gcd(12, 18)"), "Num(6, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "gcd(12, -18, 8)"), "Num(2, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "lcm(4, 6, 10)"), "Num(60, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "gcd((-2)^127, 2)"), "Num(2, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "modpow(4, 13, 497)"), "Num(445, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "modpow(2, 127, 170141183460469231731687303715884105727)"), "Num(1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "modinv(3, 11)"), "Num(4, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "modinv(-3, 11)"), "Num(7, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "isprime(97)"), "Num(1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "isprime(561)"), "Num(0, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "isprime(2305843009213693951)"), "Num(1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "nextprime(100)"), "Num(101, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "fact(20)"), "Num(2432902008176640000, []/[])".to_owned());
+        assert!((eval_as_f64(&mut env, "fact(40)") / 8.159152832478977e47 - 1.0).abs() < 1e-12);
+        assert_eq!(eval_as_string(&mut env, "binom(5, 2)"), "Num(10, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "binom(100, 50)"), "Num(100891344545564193334812497256, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "binom(3, 5)"), "Num(0, []/[])".to_owned());
+        assert!(eval_as_f64(&mut env, "fact(1e12)").is_infinite());
+        assert!(eval_as_f64(&mut env, "binom(1e12, 5e11)").is_infinite());
+        assert!((eval_as_f64(&mut env, "binom(200, 100)") / 9.054851465610328e58 - 1.0).abs() < 1e-12);
+        assert_eq!(eval_as_string(&mut env, "isqrt(99)"), "Num(9, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "isqrt(1e6)"), "Num(1000, []/[])".to_owned());
+
+        let n = parse(&mut env, &(lexer("modinv(4, 8)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "4 has no inverse modulo 8");
+        let n = parse(&mut env, &(lexer("gcd(1.5, 3)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "gcd requires integer");
+        let n = parse(&mut env, &(lexer("gcd((-2)^127, 0)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "2^127 does not fit in integer");
+        let n = parse(&mut env, &(lexer("lcm((-2)^127, 3)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "lcm overflow");
+    }
+
+    #[test]
+    fn test_rounding_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "floor(-2.5)"), "Num(-3, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "ceil(2.1)"), "Num(3, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "round(2.5)"), "Num(3, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "trunc(-2.7)"), "Num(-2, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "floor(2.7[m])"), "Num(2, [(\"m\", 1)]/[])".to_owned());
+        assert_eq!(eval_as_f64(&mut env, "round(2.71828, 2)"), 2.72);
+        assert_eq!(eval_as_string(&mut env, "round(1250, -2)"), "Num(1300, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "round(1249.9, -2)"), "Num(1200, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "roundsig(123456.0, 3)"), "Num(123000, []/[])".to_owned());
+        assert_eq!(eval_as_f64(&mut env, "roundsig(0.0012345, 2)"), 0.0012);
+        assert!((eval_as_f64(&mut env, "mround(1.23, 0.05)") - 1.25).abs() < 1e-12);
+        assert_eq!(eval_as_string(&mut env, "mround(17, 5)"), "Num(15, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "mround(-18, 5)"), "Num(-20, []/[])".to_owned());
+        assert_eq!(
+            eval_as_string(&mut env, "mround(170141183460469231731687303715884105726, 170141183460469231731687303715884105727)"),
+            "Num(170141183460469231731687303715884105727, []/[])".to_owned()
+        );
+        assert_eq!(eval_as_string(&mut env, "sign(-0.5)"), "Num(-1, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "sign(0.0)"), "Num(0, []/[])".to_owned());
+        let c = eval_as_complex64(&mut env, "sign(3+4i)");
+        assert!((c.re - 0.6).abs() < 1e-12 && (c.im - 0.8).abs() < 1e-12);
+        assert!((eval_as_f64(&mut env, "frac(-2.75)") + 0.75).abs() < 1e-12);
+        assert_eq!(eval_as_string(&mut env, "int(1e3)"), "Num(1000, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "int(2.9) % 2"), "Num(0, []/[])".to_owned());
+
+        let n = parse(&mut env, &(lexer("int(1e40)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "1e40 does not fit in integer");
+        let n = parse(&mut env, &(lexer("mround(1.5, 0)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "multiple of 0");
+        let n = parse(&mut env, &(lexer("roundsig(1e-300, 170141183460469231731687303715884105727)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "too many significant digits");
+    }
+
+    #[test]
+    fn test_statistics_functions() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_string(&mut env, "min(3,1,2)"), "FNum(1.0, []/[])".to_owned());
+        assert_eq!(eval_as_string(&mut env, "max(1[m],3[m])"), "FNum(3.0, [(\"m\", 1)]/[])".to_owned());
+        assert_eq!(eval_as_output(&mut env, "max(2[m]*1[s]/1[s], 1[m])"), "2 [m]".to_owned());
+        assert_eq!(eval_as_output(&mut env, "ave(6[m/s], 2[m/s])"), "4 [m/s]".to_owned());
+        let n = parse(&mut env, &(lexer("max()".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "max() without arguments");
+        let n = parse(&mut env, &(lexer("ave()".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "ave() without arguments");
+        assert_eq!(eval_as_f64(&mut env, "sum(1,2,3.5)"), 6.5);
+        assert_eq!(eval_as_f64(&mut env, "product(2,3,4)"), 24.0);
+        assert_eq!(eval_as_f64(&mut env, "median(5,1,3)"), 3.0);
+        assert_eq!(eval_as_f64(&mut env, "median(4,1,3,2)"), 2.5);
+        assert_eq!(eval_as_f64(&mut env, "mode(1,2,2,3,3)"), 2.0);
+        assert_eq!(eval_as_f64(&mut env, "var(2,4,4,4,5,5,7,9)"), 32.0 / 7.0);
+        assert_eq!(eval_as_f64(&mut env, "varp(2,4,4,4,5,5,7,9)"), 4.0);
+        assert_eq!(eval_as_f64(&mut env, "stdp(2,4,4,4,5,5,7,9)"), 2.0);
+        assert!((eval_as_f64(&mut env, "std(1,2,3,4)") - 1.2909944487358056).abs() < 1e-12);
+        assert!((eval_as_f64(&mut env, "rms(3,4)") - 12.5_f64.sqrt()).abs() < 1e-12);
+        assert!((eval_as_f64(&mut env, "geomean(2,8)") - 4.0).abs() < 1e-12);
+        assert!((eval_as_f64(&mut env, "harmmean(1,4,4)") - 2.0).abs() < 1e-12);
+        assert_eq!(eval_as_f64(&mut env, "percentile(25, 1,2,3,4,5)"), 2.0);
+        assert_eq!(eval_as_f64(&mut env, "percentile(90, 10,20)"), 19.0);
+
+        let n = parse(&mut env, &(lexer("ave(1, 2+i)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "complex argument should be an error");
+        let n = parse(&mut env, &(lexer("sum(1[m], 1[s])".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "different units should be an error");
+        let n = parse(&mut env, &(lexer("var(1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "sample variance requires 2 values");
+        let n = parse(&mut env, &(lexer("percentile(101, 1, 2)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "percentile should be 0..=100");
+        let n = parse(&mut env, &(lexer("geomean(1, -1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "geomean requires positive values");
+    }
+
+    fn eval_as_output(env: &mut Env, input: &str) -> String {
+        let n = parse(env, &(lexer(input.to_owned())).unwrap()).unwrap();
+        let n = eval(env, &n).unwrap();
+        output_format(env, &n)
+    }
+
+    #[test]
+    fn test_list() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_output(&mut env, "{1, 2, 3}"), "{1, 2, 3}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{1, 2, 3}*2"), "{2, 4, 6}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "10-{1, 2}"), "{9, 8}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{1, 2}+{10, 20}"), "{11, 22}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "-{1, 2}"), "{-1, -2}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{1.0[m], 3[m]}/2[s]"), "{0.5 [m/s], 1 [m/s]}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{1, {2, 3}}^2"), "{1, {4, 9}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{}"), "{}".to_owned());
+
+        // Functions of fixed arity are applied to each element
+        assert_eq!(eval_as_output(&mut env, "sqrt({4, 9})"), "{2, 3}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "round({1.5, 2.4})"), "{2, 2}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "popcount({1, 3})"), "{1, 2}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "gcd({12, 18})"), "6".to_owned());
+        assert_eq!(eval_as_output(&mut env, "bits({0x12, 0x34}, 7, 4)"), "{1, 3}".to_owned());
+
+        // Variadic functions take the elements as arguments
+        eval_as_string(&mut env, "v = {3[V], 1[V], 2[V]}");
+        assert_eq!(eval_as_output(&mut env, "max(v)"), "3 [V]".to_owned());
+        assert_eq!(eval_as_output(&mut env, "ave(v, 6[V])"), "3 [V]".to_owned());
+        assert_eq!(eval_as_output(&mut env, "median(v)"), "2 [V]".to_owned());
+        assert_eq!(eval_as_output(&mut env, "percentile(50, v)"), "2 [V]".to_owned());
+        assert_eq!(eval_as_output(&mut env, "gcd({12, 18}, 8)"), "2".to_owned());
+
+        // User function
+        eval_as_string(&mut env, "defun sq _1*_1");
+        assert_eq!(eval_as_output(&mut env, "sq({1, 2, 3})"), "{1, 4, 9}".to_owned());
+
+        let n = parse(&mut env, &(lexer("{1, 2}+{1, 2, 3}".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "list length mismatch");
+    }
+
+    #[test]
+    fn test_matrix() {
+        let mut env = Env::new();
+        env.built_in();
+
+        eval_as_string(&mut env, "A = {{2, 1}, {1, 3}}");
+        assert_eq!(eval_as_output(&mut env, "A"), "{{2, 1}, {1, 3}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "A*A"), "{{5, 5}, {5, 10}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "A*{1, 2}"), "{4, 7}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "A+A-A/2"), "{{3, 1.5}, {1.5, 4.5}}");
+        assert_eq!(eval_as_output(&mut env, "-A"), "{{-2, -1}, {-1, -3}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "-A^2"), "{{5, 5}, {5, 10}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{{1, 1}, {1, 0}}^10"), "{{89, 55}, {55, 34}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{{2, 0}, {0, 4}}^-2"), "{{0.25, 0}, {0, 0.0625}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "A^0"), "{{1, 0}, {0, 1}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "{{1, 0}, {0, 1}}^1000000000000000"), "{{1, 0}, {0, 1}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "det(A)"), "5".to_owned());
+        assert_eq!(eval_as_output(&mut env, "inv({{2, 0}, {0, 4}})"), "{{0.5, 0}, {0, 0.25}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "transpose({{1, 2, 3}, {4, 5, 6}})"), "{{1, 4}, {2, 5}, {3, 6}}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "linsolve(A, {3, 5})"), "{0.8, 1.4}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "eig({{0, -1}, {1, 0}})"), "{0-1i, 0+1i}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "eig({{2, 1}, {1, 2}})"), "{1, 3}".to_owned());
+        assert!((eval_as_complex64(&mut env, "det({{1, i}, {2, 3}})") - Complex64::new(3.0, -2.0)).norm() < 1e-12);
+
+        // Polynomial roots by the companion matrix
+        assert_eq!(eval_as_output(&mut env, "roots(1, 0, 0, 0, -1)"), "{-1, 0-1i, 0+1i, 1}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "roots({2, -4, 2})"), "{1, 1}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "roots(1, 2, 5)"), "{-1-2i, -1+2i}".to_owned());
+
+        // rows of different length are a list
+        assert_eq!(eval_as_output(&mut env, "{{1, 2}, {3}}"), "{{1, 2}, {3}}".to_owned());
+
+        let n = parse(&mut env, &(lexer("A*{{1, 2, 3}}".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "matrix size mismatch");
+        let n = parse(&mut env, &(lexer("inv({{1, 2}, {2, 4}})".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "singular matrix");
+        let n = parse(&mut env, &(lexer("{{1[m], 2}}*2".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "entry with units");
+        let n = parse(&mut env, &(lexer("det({{1[m], 0}, {0, 1[m]}})".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "entry with units");
+        let n = parse(&mut env, &(lexer("A*2[m]".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "scalar with units");
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert!((eval_as_f64(&mut env, "solve(x^3 - 2*x - 5, x, 2)") - 2.0945514815423265).abs() < 1e-12);
+        assert_eq!(eval_as_output(&mut env, "solve(x^2 - 4[m^2], x, 1[m])"), "2 [m]".to_owned());
+        assert!((eval_as_f64(&mut env, "solve(1/(2*pi*1k*c) - 100, c, 1u)") - 1.5915494309189535e-6).abs() < 1e-18);
+        assert!((eval_as_f64(&mut env, "solve(_1^2 - 3, 1)") - 3f64.sqrt()).abs() < 1e-12);
+        eval_as_string(&mut env, "defun g _1^2 - 2");
+        assert!((eval_as_f64(&mut env, "solve(g, 1)") - 2f64.sqrt()).abs() < 1e-12);
+
+        // the variable is restored
+        eval_as_string(&mut env, "y = 5");
+        assert!((eval_as_f64(&mut env, "solve(y^2 - 9, y, 1)") - 3.0).abs() < 1e-12);
+        assert_eq!(eval_as_string(&mut env, "y"), "Num(5, []/[])".to_owned());
+
+        let n = parse(&mut env, &(lexer("solve(x^2 + 1, x, 1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "no real root");
+        let n = parse(&mut env, &(lexer("solve(pi - 1, pi, 1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "constant is not a variable");
+    }
+
+    #[test]
+    fn test_integrate_diff() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert!((eval_as_f64(&mut env, "integrate(x^2, x, 0, 3)") - 9.0).abs() < 1e-12);
+        assert!((eval_as_f64(&mut env, "integrate(exp, 0, 1)") - (std::f64::consts::E - 1.0)).abs() < 1e-12);
+        assert_eq!(eval_as_output(&mut env, "integrate(5[V], t, 0[s], 2[s])"), "10 [V s]".to_owned());
+        eval_as_string(&mut env, "defun w sin(2*pi*50*_1)");
+        let rms = eval_as_f64(&mut env, "sqrt(integrate(w(t)^2, t, 0, 20m)/20m)");
+        assert!((rms - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-10);
+
+        assert!((eval_as_f64(&mut env, "diff(x^3, x, 2)") - 12.0).abs() < 1e-9);
+        assert!((eval_as_f64(&mut env, "diff(_1^2, 3)") - 6.0).abs() < 1e-9);
+        assert!((eval_as_f64(&mut env, "diff(sin, 0)") - 1.0).abs() < 1e-12);
+        assert_eq!(eval_as_output(&mut env, "round(diff(x*x*3[m/s^2]/2, x, 2[s]))"), "6 [m/s]".to_owned());
+
+        let n = parse(&mut env, &(lexer("integrate(1/x, x, 0, 1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "divergent integral");
+        let n = parse(&mut env, &(lexer("diff(x^2, x)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "missing point");
+    }
+
+    #[test]
+    fn test_minimize() {
+        let mut env = Env::new();
+        env.built_in();
+
+        // power transfer to the load of 10V, 50ohm source
+        eval_as_string(&mut env, "defun p (10/(50+_1))^2*_1");
+        assert_eq!(eval_as_output(&mut env, "map(round, maximize(p, 1, 1k)*1000)"), "{50000, 500}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(round, maximize(p(r), r, 1, 1k)*1000)"), "{50000, 500}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(round, minimize(x^2 - 4[m]*x, x, 0[m], 10[m]))"), "{2 [m], -4 [m^2]}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "minimize(x, x, 1, 2)"), "{1, 1}".to_owned());
+        assert_eq!(
+            eval_as_output(&mut env, "map(round, minimize((x-1)^2 + (y+2)^2 + 3, {x, y}, {-5, -5}, {5, 5})*1000)"),
+            "{1000, -2000, 3000}".to_owned()
+        );
+        assert_eq!(eval_as_output(&mut env, "map(round, maximize(_1*_2, {0, 0}, {2, 3}))"), "{2, 3, 6}".to_owned());
+
+        let n = parse(&mut env, &(lexer("minimize(x, {x, y}, {0, 0}, 1)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "number of bounds");
+        let n = parse(&mut env, &(lexer("minimize(x, x, 0)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "missing bound");
+    }
+
+    #[test]
+    fn test_transfer_function() {
+        let mut env = Env::new();
+        env.built_in();
+
+        // RC low-pass filter, R = 1k, C = 1u
+        eval_as_string(&mut env, "H = tf({1}, {1k*1u, 1})");
+        assert_eq!(eval_as_output(&mut env, "H"), "1/(0.001*s + 1)".to_owned());
+        assert_eq!(eval_as_output(&mut env, "tf({1, 0, -4}, {2, 0})"), "(s^2 - 4)/(2*s)".to_owned());
+        assert_eq!(eval_as_output(&mut env, "round(bodemag(H, 1/(2*pi*1m)), 4)"), "-3.0103".to_owned());
+        assert_eq!(eval_as_output(&mut env, "round(bodephase(H, 1/(2*pi*1m)), 4)"), "-0.7854".to_owned());
+        assert_eq!(eval_as_output(&mut env, "round(bandwidth(H), 4)"), "159.1549".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(round, abs(freqresp(H, {0, 1G})))"), "{1, 0}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(round, poles(H))"), "{-1000}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "zeros(tf({1, 3}, {1, 2, 5}))"), "{-3}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "poles(tf({1, 3}, {1, 2, 5}))"), "{-1-2i, -1+2i}".to_owned());
+
+        // series, parallel and scalar
+        assert_eq!(eval_as_output(&mut env, "H*H"), "1/(0.000001*s^2 + 0.002*s + 1)".to_owned());
+        assert_eq!(eval_as_output(&mut env, "H + H"), "2/(0.001*s + 1)".to_owned());
+        assert_eq!(eval_as_output(&mut env, "1 - H"), "0.001*s/(0.001*s + 1)".to_owned());
+        assert_eq!(eval_as_output(&mut env, "H^-1"), "(0.001*s + 1)/1".to_owned());
+        assert_eq!(eval_as_output(&mut env, "tf({1}, {1, 1})^3"), "1/(s^3 + 3*s^2 + 3*s + 1)".to_owned());
+
+        // zero numerator has no zeros
+        assert_eq!(eval_as_output(&mut env, "freqresp(H - H, 1)"), "0".to_owned());
+        assert_eq!(eval_as_output(&mut env, "bodemag(H - H, 1)"), "-inf".to_owned());
+        assert_eq!(eval_as_output(&mut env, "zeros(tf({0}, {1, 1}))"), "{}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "poles(tf({0}, {1, 1}))"), "{-1}".to_owned());
+
+        // loop gain of three poles, gain margin 20*log10(8/K), phase crossover at sqrt(3) rad/s
+        eval_as_string(&mut env, "L = tf({4}, {1, 3, 3, 1})");
+        assert_eq!(eval_as_output(&mut env, "map(round, gm(L)*10000)"), "{60206, 2757}".to_owned());
+        eval_as_string(&mut env, "angle deg");
+        assert_eq!(eval_as_output(&mut env, "map(round, pm(L)*10000)"), "{271417, 1962}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "round(bodephase(L, 1k))"), "-270".to_owned());
+        assert_eq!(eval_as_output(&mut env, "gm(tf({1}, {1, 1}))"), "{inf, inf}".to_owned());
+        // lead-lag loop, the phase approaches -180 deg but does not cross it
+        assert_eq!(eval_as_output(&mut env, "gm(tf({10, 1}, {1, 3, 3, 1}))"), "{inf, inf}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "pm(tf({1}, {1, 1}))"), "{inf, inf}".to_owned());
+
+        let n = parse(&mut env, &(lexer("tf({1}, {0})".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "zero denominator");
+        let n = parse(&mut env, &(lexer("tf({0}, {1})^-1".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "zero denominator by power");
+        let n = parse(&mut env, &(lexer("H^1000000000000".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "degree limit");
+        let n = parse(&mut env, &(lexer("tf({1}, {1[s], 1})".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "coefficient with units");
+        let n = parse(&mut env, &(lexer("H*2[m]".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "scalar with units");
+        let n = parse(&mut env, &(lexer("bandwidth(tf({1, 0}, {1, 1}))".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "high-pass has no DC gain");
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut env = Env::new();
+        env.built_in();
+
+        assert_eq!(eval_as_output(&mut env, "range(1, 4)"), "{1, 2, 3, 4}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "range(5, 1, -2)"), "{5, 3, 1}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "range(0, 1, 0.5)"), "{0, 0.5, 1}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "range(1[m], 2[m])"), "{1 [m], 2 [m]}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "range(2, 1)"), "{}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "linspace(0, 1, 3)"), "{0, 0.5, 1}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "logspace(0, 2, 3)"), "{1, 10, 100}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "logspace(0[Hz], 1[Hz], 2)"), "{1 [Hz], 10 [Hz]}".to_owned());
+
+        // Lambda `_1` or function name
+        assert_eq!(eval_as_output(&mut env, "map(_1*2, {1, 2, 3})"), "{2, 4, 6}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(sqrt, {4, 9})"), "{2, 3}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "map(_1*2[s], {1[m]})"), "{2 [m s]}".to_owned());
+        eval_as_string(&mut env, "defun sq _1*_1");
+        assert_eq!(eval_as_output(&mut env, "map(sq, range(1, 3))"), "{1, 4, 9}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "filter(_1%2, range(1, 6))"), "{1, 3, 5}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "filter(isprime, range(1, 10))"), "{2, 3, 5, 7}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "sum(map(sq, range(1, 3)))"), "14".to_owned());
+        eval_as_string(&mut env, "defun twice map(_1*2, _1)");
+        assert_eq!(eval_as_output(&mut env, "twice({1, 2})"), "{2, 4}".to_owned());
+        eval_as_string(&mut env, "defun scale map(_1*_2, _1)");
+        assert_eq!(eval_as_output(&mut env, "scale({1, 2}, 3)"), "{3, 6}".to_owned());
+        eval_as_string(&mut env, "defun odd filter(_1%2, range(1, _1))");
+        assert_eq!(eval_as_output(&mut env, "odd(5)"), "{1, 3, 5}".to_owned());
+        assert_eq!(eval_as_output(&mut env, "prod(range(1, 5))"), "120".to_owned());
+
+        let n = parse(&mut env, &(lexer("range(1, 2, 0)".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "zero step");
+        let n = parse(&mut env, &(lexer("map(_2, {1})".to_owned())).unwrap()).unwrap();
+        assert!(eval(&mut env, &n).is_err(), "unknown lambda parameter");
+    }
+
     #[test]
     fn test_assignment_errors() {
         let mut env = Env::new();
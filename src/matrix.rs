@@ -0,0 +1,622 @@
+use super::*;
+use std::cmp::Ordering;
+
+// Matrix values are computed as complex numbers, entries must not have units.
+type CMatrix = Vec<Vec<Complex64>>;
+
+fn matrix_cvalue(env: &Env, n: &Node) -> Result<Complex64, MyError> {
+    let units = match n {
+        Node::Num(_, units) | Node::FNum(_, units) | Node::CNum(_, units) => units,
+        _ => return eval_cvalue(env, n),
+    };
+    let unitless = match &**units {
+        Node::Units(u) => match &**u {
+            Node::None => true,
+            Node::UnitsFraction(nume, denom) => nume.keys().chain(denom.keys()).all(|k| k == "_"),
+            _ => false,
+        },
+        _ => false,
+    };
+    if unitless {
+        eval_cvalue(env, n)
+    } else {
+        Err(MyError::EvalError(format!(
+            "matrix entry with units: {:?}",
+            n
+        )))
+    }
+}
+
+fn matrix_values(env: &Env, rows: &[Vec<Node>]) -> Result<CMatrix, MyError> {
+    let mut ret = Vec::new();
+    for row in rows {
+        let mut values = Vec::new();
+        for entry in row {
+            values.push(matrix_cvalue(env, entry)?);
+        }
+        ret.push(values);
+    }
+    Ok(ret)
+}
+
+fn vector_values(env: &Env, items: &[Node]) -> Result<Vec<Complex64>, MyError> {
+    items.iter().map(|item| matrix_cvalue(env, item)).collect()
+}
+
+fn matrix_scalar(c: Complex64) -> Node {
+    let units = Box::new(Node::Units(Box::new(Node::None)));
+    if c.im == 0.0 {
+        Node::FNum(c.re, units)
+    } else {
+        Node::CNum(c, units)
+    }
+}
+
+fn matrix_node(m: &[Vec<Complex64>]) -> Node {
+    Node::Matrix(
+        m.iter()
+            .map(|row| row.iter().map(|c| matrix_scalar(*c)).collect())
+            .collect(),
+    )
+}
+
+fn vector_node(v: &[Complex64]) -> Node {
+    Node::List(v.iter().map(|c| matrix_scalar(*c)).collect())
+}
+
+fn matrix_is_square(m: &[Vec<Complex64>]) -> bool {
+    m.iter().all(|row| row.len() == m.len())
+}
+
+fn matrix_identity(n: usize) -> CMatrix {
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| Complex64::new(if i == j { 1.0 } else { 0.0 }, 0.0))
+                .collect()
+        })
+        .collect()
+}
+
+// Frobenius norm
+fn matrix_norm(m: &[Vec<Complex64>]) -> f64 {
+    m.iter().flatten().map(|c| c.norm_sqr()).sum::<f64>().sqrt()
+}
+
+fn matrix_mul(a: &[Vec<Complex64>], b: &[Vec<Complex64>]) -> Option<CMatrix> {
+    if a.is_empty() || b.is_empty() || a[0].len() != b.len() {
+        return None;
+    }
+    let mut ret = vec![vec![Complex64::new(0.0, 0.0); b[0].len()]; a.len()];
+    for (i, row) in a.iter().enumerate() {
+        for (k, x) in row.iter().enumerate() {
+            for (j, y) in b[k].iter().enumerate() {
+                ret[i][j] += x * y;
+            }
+        }
+    }
+    Some(ret)
+}
+
+// LU decomposition with partial pivoting: (LU, row permutation, sign of permutation).
+// `None` if the matrix is singular.
+fn matrix_lu(m: &[Vec<Complex64>]) -> Option<(CMatrix, Vec<usize>, f64)> {
+    let n = m.len();
+    let tolerance = matrix_norm(m) * f64::EPSILON * n as f64;
+    let mut lu = m.to_vec();
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut sign = 1.0;
+    for k in 0..n {
+        let pivot = (k..n).max_by(|&i, &j| {
+            lu[i][k]
+                .norm()
+                .partial_cmp(&lu[j][k].norm())
+                .unwrap_or(Ordering::Equal)
+        })?;
+        if lu[pivot][k].norm() <= tolerance {
+            return None;
+        }
+        if pivot != k {
+            lu.swap(pivot, k);
+            perm.swap(pivot, k);
+            sign = -sign;
+        }
+        let pivot_row = lu[k].clone();
+        for row in lu.iter_mut().skip(k + 1) {
+            let f = row[k] / pivot_row[k];
+            row[k] = f;
+            for (x, p) in row.iter_mut().zip(pivot_row.iter()).skip(k + 1) {
+                *x -= f * p;
+            }
+        }
+    }
+    Some((lu, perm, sign))
+}
+
+fn matrix_det(m: &[Vec<Complex64>]) -> Complex64 {
+    match matrix_lu(m) {
+        Some((lu, _, sign)) => {
+            (0..lu.len()).fold(Complex64::new(sign, 0.0), |det, i| det * lu[i][i])
+        }
+        None => Complex64::new(0.0, 0.0),
+    }
+}
+
+// Solve A X = B, B has n rows.
+fn matrix_solve(a: &[Vec<Complex64>], b: &[Vec<Complex64>]) -> Option<CMatrix> {
+    let n = a.len();
+    if b.len() != n {
+        return None;
+    }
+    let (lu, perm, _) = matrix_lu(a)?;
+    let mut x: CMatrix = perm.iter().map(|&p| b[p].clone()).collect();
+    for i in 0..n {
+        for k in 0..i {
+            let xk = x[k].clone();
+            for (xi, xk) in x[i].iter_mut().zip(xk.iter()) {
+                *xi -= lu[i][k] * xk;
+            }
+        }
+    }
+    for i in (0..n).rev() {
+        for k in (i + 1)..n {
+            let xk = x[k].clone();
+            for (xi, xk) in x[i].iter_mut().zip(xk.iter()) {
+                *xi -= lu[i][k] * xk;
+            }
+        }
+        for xi in x[i].iter_mut() {
+            *xi /= lu[i][i];
+        }
+    }
+    Some(x)
+}
+
+fn matrix_inverse(m: &[Vec<Complex64>]) -> Option<CMatrix> {
+    matrix_solve(m, &matrix_identity(m.len()))
+}
+
+// Householder QR decomposition: (Q, R)
+fn matrix_qr(m: &[Vec<Complex64>]) -> (CMatrix, CMatrix) {
+    let n = m.len();
+    let mut r = m.to_vec();
+    let mut q = matrix_identity(n);
+    for k in 0..n.saturating_sub(1) {
+        let norm = (k..n).map(|i| r[i][k].norm_sqr()).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let phase = if r[k][k].norm() == 0.0 {
+            Complex64::new(1.0, 0.0)
+        } else {
+            r[k][k] / r[k][k].norm()
+        };
+        let mut v: Vec<Complex64> = (k..n).map(|i| r[i][k]).collect();
+        v[0] += phase * norm;
+        let v_norm = v.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt();
+        for x in v.iter_mut() {
+            *x /= v_norm;
+        }
+        // R = (I - 2 v v^H) R
+        let s: Vec<Complex64> = (0..n)
+            .map(|j| (k..n).map(|i| v[i - k].conj() * r[i][j]).sum())
+            .collect();
+        for (i, row) in r.iter_mut().enumerate().skip(k) {
+            for (x, s) in row.iter_mut().zip(s.iter()) {
+                *x -= 2.0 * v[i - k] * s;
+            }
+        }
+        // Q = Q (I - 2 v v^H)
+        for row in q.iter_mut() {
+            let s: Complex64 = (k..n).map(|j| row[j] * v[j - k]).sum();
+            for j in k..n {
+                row[j] -= 2.0 * s * v[j - k].conj();
+            }
+        }
+    }
+    (q, r)
+}
+
+// eigenvalues of [[a, b], [c, d]]
+fn eigenvalues_2x2(a: Complex64, b: Complex64, c: Complex64, d: Complex64) -> [Complex64; 2] {
+    let mean = (a + d) / 2.0;
+    let diff = ((a - d) / 2.0 * (a - d) / 2.0 + b * c).sqrt();
+    [mean + diff, mean - diff]
+}
+
+// Shifted QR iteration, the last row is deflated when it converges.
+fn matrix_eigenvalues(m: &[Vec<Complex64>]) -> Option<Vec<Complex64>> {
+    let norm = matrix_norm(m);
+    let mut h = m.to_vec();
+    let mut ret = Vec::new();
+    let mut iteration = 0;
+    while !h.is_empty() {
+        let n = h.len();
+        if n == 1 {
+            ret.push(h[0][0]);
+            break;
+        }
+        if n == 2 {
+            ret.extend_from_slice(&eigenvalues_2x2(h[0][0], h[0][1], h[1][0], h[1][1]));
+            break;
+        }
+        let off = h[n - 1][..n - 1].iter().map(|x| x.norm()).sum::<f64>();
+        if off <= norm * 1e-15 {
+            ret.push(h[n - 1][n - 1]);
+            h.truncate(n - 1);
+            for row in h.iter_mut() {
+                row.truncate(n - 1);
+            }
+            iteration = 0;
+            continue;
+        }
+        iteration += 1;
+        if iteration > 1000 {
+            return None;
+        }
+        // Wilkinson shift, with an exceptional shift to escape from a cycle
+        let [l1, l2] = eigenvalues_2x2(
+            h[n - 2][n - 2],
+            h[n - 2][n - 1],
+            h[n - 1][n - 2],
+            h[n - 1][n - 1],
+        );
+        let mut shift = if (l1 - h[n - 1][n - 1]).norm() < (l2 - h[n - 1][n - 1]).norm() {
+            l1
+        } else {
+            l2
+        };
+        if iteration % 10 == 0 {
+            shift += off;
+        }
+        for (i, row) in h.iter_mut().enumerate() {
+            row[i] -= shift;
+        }
+        let (q, r) = matrix_qr(&h);
+        h = matrix_mul(&r, &q)?;
+        for (i, row) in h.iter_mut().enumerate() {
+            row[i] += shift;
+        }
+    }
+    // round off errors
+    let tolerance = norm * 1e-12;
+    for c in ret.iter_mut() {
+        if c.im.abs() <= tolerance {
+            c.im = 0.0;
+        }
+        if c.re.abs() <= tolerance {
+            c.re = 0.0;
+        }
+    }
+    ret.sort_by(|a, b| {
+        a.re.partial_cmp(&b.re)
+            .unwrap_or(Ordering::Equal)
+            .then(a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal))
+    });
+    Some(ret)
+}
+
+fn matrix_binop_error(tok: &Token, lhs: &Node, rhs: &Node) -> MyError {
+    MyError::EvalError(format!("matrix operator {:?}: {:?} {:?}", tok, lhs, rhs))
+}
+
+/// Matrix operators: `+`, `-` of the same size, `*` of matrix/vector (list)/scalar,
+/// `/` by scalar and `^` by integer, negative power is power of inverse.
+pub fn eval_matrix_binop(env: &Env, tok: &Token, lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
+    let error = || matrix_binop_error(tok, lhs, rhs);
+    match (tok, lhs, rhs) {
+        (Token::Op(TokenOp::Plus), Node::Matrix(l), Node::Matrix(r))
+        | (Token::Op(TokenOp::Minus), Node::Matrix(l), Node::Matrix(r)) => {
+            let (l, r) = (matrix_values(env, l)?, matrix_values(env, r)?);
+            if l.len() != r.len() || l[0].len() != r[0].len() {
+                return Err(error());
+            }
+            let sign = if *tok == Token::Op(TokenOp::Plus) {
+                1.0
+            } else {
+                -1.0
+            };
+            let ret: CMatrix = l
+                .iter()
+                .zip(r.iter())
+                .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| x + sign * y).collect())
+                .collect();
+            Ok(matrix_node(&ret))
+        }
+        (Token::Op(TokenOp::Mul), Node::Matrix(l), Node::Matrix(r)) => {
+            let ret =
+                matrix_mul(&matrix_values(env, l)?, &matrix_values(env, r)?).ok_or_else(error)?;
+            Ok(matrix_node(&ret))
+        }
+        (Token::Op(TokenOp::Mul), Node::Matrix(l), Node::List(r)) => {
+            let column: CMatrix = vector_values(env, r)?
+                .into_iter()
+                .map(|c| vec![c])
+                .collect();
+            let ret = matrix_mul(&matrix_values(env, l)?, &column).ok_or_else(error)?;
+            Ok(vector_node(
+                &ret.into_iter().map(|row| row[0]).collect::<Vec<_>>(),
+            ))
+        }
+        (Token::Op(TokenOp::Mul), Node::List(l), Node::Matrix(r)) => {
+            let row = vec![vector_values(env, l)?];
+            let ret = matrix_mul(&row, &matrix_values(env, r)?).ok_or_else(error)?;
+            Ok(vector_node(&ret[0]))
+        }
+        (Token::Op(TokenOp::Mul), Node::Matrix(m), scalar)
+        | (Token::Op(TokenOp::Mul), scalar, Node::Matrix(m)) => {
+            let k = matrix_cvalue(env, scalar).map_err(|_| error())?;
+            let ret: CMatrix = matrix_values(env, m)?
+                .iter()
+                .map(|row| row.iter().map(|x| x * k).collect())
+                .collect();
+            Ok(matrix_node(&ret))
+        }
+        (Token::Op(TokenOp::Div), Node::Matrix(m), scalar) => {
+            let k = matrix_cvalue(env, scalar).map_err(|_| error())?;
+            let ret: CMatrix = matrix_values(env, m)?
+                .iter()
+                .map(|row| row.iter().map(|x| x / k).collect())
+                .collect();
+            Ok(matrix_node(&ret))
+        }
+        (Token::Op(TokenOp::Caret), Node::Matrix(m), Node::Num(p, _)) => {
+            let mut m = matrix_values(env, m)?;
+            if !matrix_is_square(&m) {
+                return Err(error());
+            }
+            if *p < 0 {
+                m = matrix_inverse(&m).ok_or_else(error)?;
+            }
+            // square-and-multiply
+            let mut ret = matrix_identity(m.len());
+            let mut exponent = p.unsigned_abs();
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    ret = matrix_mul(&ret, &m).ok_or_else(error)?;
+                }
+                exponent >>= 1;
+                if exponent > 0 {
+                    m = matrix_mul(&m, &m).ok_or_else(error)?;
+                }
+            }
+            Ok(matrix_node(&ret))
+        }
+        _ => Err(error()),
+    }
+}
+
+fn matrix_arg(env: &Env, arg: &Node) -> Option<CMatrix> {
+    match arg {
+        Node::Matrix(rows) => matrix_values(env, rows).ok(),
+        _ => None,
+    }
+}
+
+fn square_matrix_arg(env: &Env, arg: &Node) -> Option<CMatrix> {
+    matrix_arg(env, arg).filter(|m| matrix_is_square(m))
+}
+
+pub fn impl_transpose(_env: &mut Env, arg: &[Node]) -> Node {
+    match &arg[0] {
+        Node::Matrix(rows) => Node::Matrix(
+            (0..rows[0].len())
+                .map(|j| rows.iter().map(|row| row[j].clone()).collect())
+                .collect(),
+        ),
+        _ => Node::None,
+    }
+}
+
+pub fn impl_det(env: &mut Env, arg: &[Node]) -> Node {
+    match square_matrix_arg(env, &arg[0]) {
+        Some(m) => matrix_scalar(matrix_det(&m)),
+        None => Node::None,
+    }
+}
+
+pub fn impl_inv(env: &mut Env, arg: &[Node]) -> Node {
+    match square_matrix_arg(env, &arg[0]).and_then(|m| matrix_inverse(&m)) {
+        Some(m) => matrix_node(&m),
+        None => Node::None,
+    }
+}
+
+/// linsolve(A, b): x of A x = b, `b` is a vector (list) or a matrix.
+pub fn impl_linsolve(env: &mut Env, arg: &[Node]) -> Node {
+    if arg.len() != 2 {
+        return Node::None;
+    }
+    let a = match square_matrix_arg(env, &arg[0]) {
+        Some(a) => a,
+        None => return Node::None,
+    };
+    match &arg[1] {
+        Node::List(items) => {
+            let b: CMatrix = match vector_values(env, items) {
+                Ok(v) => v.into_iter().map(|c| vec![c]).collect(),
+                Err(_) => return Node::None,
+            };
+            match matrix_solve(&a, &b) {
+                Some(x) => vector_node(&x.into_iter().map(|row| row[0]).collect::<Vec<_>>()),
+                None => Node::None,
+            }
+        }
+        b => match matrix_arg(env, b).and_then(|b| matrix_solve(&a, &b)) {
+            Some(x) => matrix_node(&x),
+            None => Node::None,
+        },
+    }
+}
+
+pub fn impl_eig(env: &mut Env, arg: &[Node]) -> Node {
+    match square_matrix_arg(env, &arg[0]).and_then(|m| matrix_eigenvalues(&m)) {
+        Some(values) => vector_node(&values),
+        None => Node::None,
+    }
+}
+
+/// (p(z), p'(z)) by Horner's method, coefficients are from the highest degree.
+pub fn poly_value(coef: &[Complex64], z: Complex64) -> (Complex64, Complex64) {
+    let mut p = Complex64::new(0.0, 0.0);
+    let mut dp = Complex64::new(0.0, 0.0);
+    for a in coef {
+        dp = dp * z + p;
+        p = p * z + a;
+    }
+    (p, dp)
+}
+
+/// Roots of the polynomial, coefficients are from the highest degree.
+/// Eigenvalues of the companion matrix, polished by Newton's method.
+pub fn poly_roots(coef: &[Complex64]) -> Option<Vec<Complex64>> {
+    let zero = Complex64::new(0.0, 0.0);
+    let leading = coef.iter().position(|a| *a != zero)?;
+    let coef = &coef[leading..];
+    let zero_roots = coef.iter().rev().take_while(|a| **a == zero).count();
+    let monic: Vec<Complex64> = coef[..coef.len() - zero_roots]
+        .iter()
+        .map(|a| a / coef[0])
+        .collect();
+    let n = monic.len() - 1;
+    let mut ret = vec![zero; zero_roots];
+    if n == 0 {
+        return Some(ret);
+    }
+    let mut companion = vec![vec![zero; n]; n];
+    for (j, a) in monic.iter().skip(1).enumerate() {
+        companion[0][j] = -a;
+    }
+    for i in 1..n {
+        companion[i][i - 1] = Complex64::new(1.0, 0.0);
+    }
+    for mut z in matrix_eigenvalues(&companion)? {
+        for _ in 0..3 {
+            let (p, dp) = poly_value(&monic, z);
+            if dp == zero {
+                break;
+            }
+            let next = z - p / dp;
+            if poly_value(&monic, next).0.norm() >= p.norm() {
+                break;
+            }
+            z = next;
+        }
+        if z.im.abs() <= 1e-12 * z.norm() {
+            z.im = 0.0;
+        }
+        ret.push(z);
+    }
+    ret.sort_by(|a, b| {
+        a.re.partial_cmp(&b.re)
+            .unwrap_or(Ordering::Equal)
+            .then(a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal))
+    });
+    Some(ret)
+}
+
+/// roots(a_n, ..., a_1, a_0): all roots of a_n x^n + ... + a_1 x + a_0, including complex roots.
+/// Coefficients may be a list, i.e. roots({1, 0, -1}).
+pub fn impl_roots(env: &mut Env, arg: &[Node]) -> Node {
+    let coef = match arg {
+        [Node::List(items)] => items.as_slice(),
+        _ => arg,
+    };
+    match vector_values(env, coef).ok().and_then(|c| poly_roots(&c)) {
+        Some(roots) => vector_node(&roots),
+        None => Node::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(re: f64, im: f64) -> Complex64 {
+        Complex64::new(re, im)
+    }
+
+    fn real_matrix(m: &[&[f64]]) -> CMatrix {
+        m.iter()
+            .map(|row| row.iter().map(|x| c(*x, 0.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_matrix_det_inverse() {
+        let m = real_matrix(&[&[2.0, 1.0], &[1.0, 3.0]]);
+        assert!((matrix_det(&m) - c(5.0, 0.0)).norm() < 1e-12);
+        let inv = matrix_inverse(&m).unwrap();
+        let id = matrix_mul(&m, &inv).unwrap();
+        assert!((id[0][0] - c(1.0, 0.0)).norm() < 1e-12);
+        assert!(id[0][1].norm() < 1e-12);
+
+        // pivoting
+        let m = real_matrix(&[&[0.0, 1.0], &[1.0, 0.0]]);
+        assert!((matrix_det(&m) - c(-1.0, 0.0)).norm() < 1e-12);
+
+        let singular = real_matrix(&[&[1.0, 2.0], &[2.0, 4.0]]);
+        assert_eq!(matrix_det(&singular), c(0.0, 0.0));
+        assert!(matrix_inverse(&singular).is_none());
+    }
+
+    #[test]
+    fn test_matrix_eigenvalues() {
+        let m = real_matrix(&[&[2.0, 0.0, 0.0], &[0.0, 3.0, 4.0], &[0.0, 4.0, 9.0]]);
+        let values = matrix_eigenvalues(&m).unwrap();
+        for (value, expected) in values.iter().zip([1.0, 2.0, 11.0].iter()) {
+            assert!((value - c(*expected, 0.0)).norm() < 1e-10, "{:?}", values);
+        }
+
+        // rotation has complex eigenvalues
+        let m = real_matrix(&[&[0.0, -1.0], &[1.0, 0.0]]);
+        assert_eq!(
+            matrix_eigenvalues(&m).unwrap(),
+            vec![c(0.0, -1.0), c(0.0, 1.0)]
+        );
+
+        // companion matrix of (x-1)(x-2)(x-3)(x-4)
+        let m = real_matrix(&[
+            &[10.0, -35.0, 50.0, -24.0],
+            &[1.0, 0.0, 0.0, 0.0],
+            &[0.0, 1.0, 0.0, 0.0],
+            &[0.0, 0.0, 1.0, 0.0],
+        ]);
+        let values = matrix_eigenvalues(&m).unwrap();
+        for (value, expected) in values.iter().zip([1.0, 2.0, 3.0, 4.0].iter()) {
+            assert!((value - c(*expected, 0.0)).norm() < 1e-9, "{:?}", values);
+        }
+
+        assert_eq!(
+            matrix_eigenvalues(&matrix_identity(3)).unwrap(),
+            vec![c(1.0, 0.0); 3]
+        );
+    }
+
+    #[test]
+    fn test_poly_roots() {
+        let roots = poly_roots(&[c(1.0, 0.0), c(-6.0, 0.0), c(11.0, 0.0), c(-6.0, 0.0)]).unwrap();
+        for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0].iter()) {
+            assert!((root - c(*expected, 0.0)).norm() < 1e-12, "{:?}", roots);
+            assert_eq!(root.im, 0.0);
+        }
+
+        // 2nd order Butterworth poles
+        let roots = poly_roots(&[c(1.0, 0.0), c(2f64.sqrt(), 0.0), c(1.0, 0.0)]).unwrap();
+        let r = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((roots[0] - c(-r, -r)).norm() < 1e-12, "{:?}", roots);
+        assert!((roots[1] - c(-r, r)).norm() < 1e-12, "{:?}", roots);
+
+        // leading and trailing zeros
+        let roots = poly_roots(&[c(0.0, 0.0), c(2.0, 0.0), c(-2.0, 0.0), c(0.0, 0.0)]).unwrap();
+        assert_eq!(roots, vec![c(0.0, 0.0), c(1.0, 0.0)]);
+
+        // complex coefficients: (x - i)(x + 2)
+        let roots = poly_roots(&[c(1.0, 0.0), c(2.0, -1.0), c(0.0, -2.0)]).unwrap();
+        assert!((roots[0] - c(-2.0, 0.0)).norm() < 1e-12, "{:?}", roots);
+        assert!((roots[1] - c(0.0, 1.0)).norm() < 1e-12, "{:?}", roots);
+
+        assert_eq!(poly_roots(&[c(3.0, 0.0)]).unwrap(), vec![]);
+        assert!(poly_roots(&[c(0.0, 0.0)]).is_none());
+    }
+}
@@ -0,0 +1,1132 @@
+use super::*;
+use std::cmp::Ordering;
+
+// The name of variable of the expression, constants such as `pi` can not be a variable.
+fn variable_name(env: &Env, n: &Node) -> Result<String, MyError> {
+    match n {
+        Node::Var(Token::Ident(id)) if env.is_const(id).is_none() => Ok(id.clone()),
+        _ => Err(MyError::EvalError(format!("variable expected: {:?}", n))),
+    }
+}
+
+fn real_value(env: &mut Env, n: &Node) -> Result<(f64, Box<Node>), MyError> {
+    match eval(env, n)? {
+        Node::Num(v, units) => Ok((v as f64, units)),
+        Node::FNum(f, units) => Ok((f, units)),
+        other => Err(MyError::EvalError(format!(
+            "real value expected: {:?}",
+            other
+        ))),
+    }
+}
+
+// (f, var, values) of `name(f, values...)` or `name(expr, var, values...)`
+fn function_args<'a>(
+    env: &Env,
+    name: &str,
+    arg: &'a [Node],
+    n_values: usize,
+) -> Result<(&'a Node, Option<String>, &'a [Node]), MyError> {
+    if arg.len() == n_values + 1 {
+        Ok((&arg[0], None, &arg[1..]))
+    } else if arg.len() == n_values + 2 {
+        Ok((&arg[0], Some(variable_name(env, &arg[1])?), &arg[2..]))
+    } else {
+        Err(MyError::EvalError(format!(
+            "{} requires {} or {} parameter(s): {:?}",
+            name,
+            n_values + 1,
+            n_values + 2,
+            arg
+        )))
+    }
+}
+
+// Value of `f` at `x`. `f` is an expression of `var`, or a function name or a lambda of `_1`
+// when `var` is `None`. `x` has the units of the initial value.
+fn eval_node_at(
+    env: &mut Env,
+    f: &Node,
+    var: Option<&str>,
+    x: f64,
+    units: &Node,
+) -> Result<Node, MyError> {
+    let value = Node::FNum(x, Box::new(units.clone()));
+    match var {
+        Some(var) => eval_with_variable(env, var, value, f),
+        None => apply_lambda(env, f, value),
+    }
+}
+
+fn eval_real_at(
+    env: &mut Env,
+    f: &Node,
+    var: Option<&str>,
+    x: f64,
+    units: &Node,
+) -> Result<f64, MyError> {
+    let y = eval_node_at(env, f, var, x, units)?;
+    eval_fvalue(env, &y)
+}
+
+// `value` with units of (units of `f`) `op` (units of `x`), i.e. [V] * [s] of integral
+fn eval_units_result(
+    env: &mut Env,
+    value: f64,
+    f_value: &Node,
+    op: TokenOp,
+    x_units: Box<Node>,
+) -> Result<Node, MyError> {
+    let f_units = match f_value {
+        Node::Num(_, units) | Node::FNum(_, units) => units.clone(),
+        _ => {
+            return Err(MyError::EvalError(format!(
+                "real value expected: {:?}",
+                f_value
+            )))
+        }
+    };
+    eval(
+        env,
+        &Node::BinOp(
+            Token::Op(op),
+            Box::new(Node::FNum(value, f_units)),
+            Box::new(Node::FNum(1.0, x_units)),
+        ),
+    )
+}
+
+// Narrow the bracket [a, b] of a sign change by secant, bisection when secant is slow.
+fn refine_bracket<F>(f: &mut F, mut a: f64, mut fa: f64, mut b: f64, mut fb: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    for _ in 0..200 {
+        let width = (b - a).abs();
+        let mut x = b - fb * (b - a) / (fb - fa);
+        if !(x > a.min(b) && x < a.max(b)) {
+            x = (a + b) / 2.0;
+        }
+        for _ in 0..2 {
+            let fx = f(x)?;
+            if fx == 0.0 {
+                return Some(x);
+            }
+            if fx.signum() == fa.signum() {
+                a = x;
+                fa = fx;
+            } else {
+                b = x;
+                fb = fx;
+            }
+            if (b - a).abs() <= width / 2.0 {
+                break;
+            }
+            x = (a + b) / 2.0;
+        }
+        if (b - a).abs() <= 2.0 * f64::EPSILON * a.abs().max(b.abs()) {
+            break;
+        }
+    }
+    Some(if fa.abs() < fb.abs() { a } else { b })
+}
+
+// Newton's method with numerical derivative.
+fn newton<F>(f: &mut F, x0: f64, scale: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    let mut x = x0;
+    for _ in 0..100 {
+        let y = f(x)?;
+        if y == 0.0 {
+            return Some(x);
+        }
+        let h = 1e-7 * x.abs().max(scale);
+        let dy = (f(x + h)? - f(x - h)?) / (2.0 * h);
+        if dy == 0.0 || !dy.is_finite() {
+            return None;
+        }
+        let dx = y / dy;
+        x -= dx;
+        if dx.abs() <= 1e-12 * x.abs().max(scale) {
+            return Some(x);
+        }
+    }
+    None
+}
+
+/// Root of `f` near `x0`. A sign change is searched around `x0` and the bracket is narrowed.
+/// Newton's method is used when there is no sign change, i.e. double root.
+/// `f` returns `None` when the value is not available.
+pub fn find_root<F>(mut f: F, x0: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    let f0 = f(x0)?;
+    if f0 == 0.0 {
+        return Some(x0);
+    }
+    let scale = if x0 == 0.0 { 1.0 } else { x0.abs() };
+    let mut step = scale * 1e-3;
+    for _ in 0..80 {
+        for x in [x0 + step, x0 - step].iter() {
+            if let Some(y) = f(*x) {
+                if y == 0.0 {
+                    return Some(*x);
+                }
+                if y.signum() != f0.signum() {
+                    let root = refine_bracket(&mut f, x0, f0, *x, y)?;
+                    // a sign change at a pole is not a root
+                    if f(root)?.abs() <= f0.abs().max(y.abs()) {
+                        return Some(root);
+                    }
+                }
+            }
+        }
+        step *= 2.0;
+    }
+    newton(&mut f, x0, scale)
+}
+
+/// solve(expr, x, guess): root of the expression of `x` near `guess`.
+/// solve(f, guess): root of the function `f` or the lambda of `_1`.
+/// The root has the units of `guess`.
+pub fn impl_solve(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    let (f, var, values) = function_args(env, "solve", arg, 1)?;
+    let var = var.as_deref();
+    let (x0, units) = real_value(env, &values[0])?;
+    // error of the expression itself is reported
+    eval_real_at(env, f, var, x0, &units)?;
+    let root = find_root(
+        |x| {
+            eval_real_at(env, f, var, x, &units)
+                .ok()
+                .filter(|y| y.is_finite())
+        },
+        x0,
+    );
+    match root {
+        Some(root) => Ok(Node::FNum(root, units)),
+        None => Err(MyError::EvalError(format!(
+            "solve: no root found near {}",
+            x0
+        ))),
+    }
+}
+
+// Gauss-Kronrod 15 points, nodes and weights of QUADPACK
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0.0,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_2,
+    0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_8,
+];
+// Gauss 7 points, nodes are KRONROD_NODES[1], [3], [5] and [7]
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+
+// (integral, error estimate) on [a, b]
+fn gauss_kronrod<F>(f: &mut F, a: f64, b: f64) -> Option<(f64, f64)>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    let center = (a + b) / 2.0;
+    let half = (b - a) / 2.0;
+    let fc = f(center)?;
+    let mut kronrod = fc * KRONROD_WEIGHTS[7];
+    let mut gauss = fc * GAUSS_WEIGHTS[3];
+    for j in 0..7 {
+        let dx = half * KRONROD_NODES[j];
+        let sum = f(center - dx)? + f(center + dx)?;
+        kronrod += KRONROD_WEIGHTS[j] * sum;
+        if j % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[j / 2] * sum;
+        }
+    }
+    Some((kronrod * half, ((kronrod - gauss) * half).abs()))
+}
+
+/// Integral of `f` on [a, b] by adaptive Gauss-Kronrod quadrature.
+/// The interval of the largest error is bisected until the error is small enough.
+/// `f` is not evaluated at the ends, so that 1/sqrt(x) on [0, 1] can be integrated.
+pub fn integrate<F>(mut f: F, a: f64, b: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    let (integral, error) = gauss_kronrod(&mut f, a, b)?;
+    let mut intervals = vec![(a, b, integral, error)];
+    for _ in 0..2000 {
+        let total: f64 = intervals.iter().map(|i| i.2).sum();
+        let error: f64 = intervals.iter().map(|i| i.3).sum();
+        let scale: f64 = intervals.iter().map(|i| i.2.abs()).sum();
+        if error <= 1e-10 * scale {
+            return Some(total);
+        }
+        let (index, _) = intervals
+            .iter()
+            .enumerate()
+            .max_by(|x, y| (x.1).3.partial_cmp(&(y.1).3).unwrap_or(Ordering::Equal))?;
+        let (a, b, _, _) = intervals.swap_remove(index);
+        let center = (a + b) / 2.0;
+        let (integral, error) = gauss_kronrod(&mut f, a, center)?;
+        intervals.push((a, center, integral, error));
+        let (integral, error) = gauss_kronrod(&mut f, center, b)?;
+        intervals.push((center, b, integral, error));
+    }
+    None
+}
+
+/// Derivative of `f` at `x` by central differences with Richardson extrapolation (Ridders).
+pub fn derivative<F>(mut f: F, x: f64) -> Option<f64>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    const N: usize = 10;
+    const CON: f64 = 1.4;
+    let mut h = 0.1 * if x == 0.0 { 1.0 } else { x.abs() };
+    let mut table = [[0.0; N]; N];
+    table[0][0] = (f(x + h)? - f(x - h)?) / (2.0 * h);
+    let mut ret = table[0][0];
+    let mut error = f64::INFINITY;
+    for i in 1..N {
+        h /= CON;
+        table[i][0] = (f(x + h)? - f(x - h)?) / (2.0 * h);
+        let mut factor = CON * CON;
+        for j in 1..=i {
+            table[i][j] = (table[i][j - 1] * factor - table[i - 1][j - 1]) / (factor - 1.0);
+            factor *= CON * CON;
+            let e = (table[i][j] - table[i][j - 1])
+                .abs()
+                .max((table[i][j] - table[i - 1][j - 1]).abs());
+            if e <= error {
+                error = e;
+                ret = table[i][j];
+            }
+        }
+        // higher order is worse
+        if (table[i][i] - table[i - 1][i - 1]).abs() >= 2.0 * error {
+            break;
+        }
+    }
+    Some(ret)
+}
+
+/// integrate(expr, x, a, b) or integrate(f, a, b): integral of the expression of `x` on [a, b].
+/// The result has the units of the expression times the units of `a`.
+pub fn impl_integrate(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    let (f, var, values) = function_args(env, "integrate", arg, 2)?;
+    let var = var.as_deref();
+    let (a, units) = real_value(env, &values[0])?;
+    let (b, _) = real_value(env, &values[1])?;
+    let f_value = eval_node_at(env, f, var, (a + b) / 2.0, &units)?;
+    eval_fvalue(env, &f_value)?;
+    let integral = integrate(
+        |x| {
+            eval_real_at(env, f, var, x, &units)
+                .ok()
+                .filter(|y| y.is_finite())
+        },
+        a,
+        b,
+    );
+    match integral {
+        Some(integral) => eval_units_result(env, integral, &f_value, TokenOp::Mul, units),
+        None => Err(MyError::EvalError(format!(
+            "integrate: not converged on [{}, {}]",
+            a, b
+        ))),
+    }
+}
+
+/// diff(expr, x, x0) or diff(f, x0): derivative of the expression of `x` at `x0`.
+/// The result has the units of the expression divided by the units of `x0`.
+pub fn impl_diff(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    let (f, var, values) = function_args(env, "diff", arg, 1)?;
+    let var = var.as_deref();
+    let (x0, units) = real_value(env, &values[0])?;
+    let f_value = eval_node_at(env, f, var, x0, &units)?;
+    eval_fvalue(env, &f_value)?;
+    let slope = derivative(
+        |x| {
+            eval_real_at(env, f, var, x, &units)
+                .ok()
+                .filter(|y| y.is_finite())
+        },
+        x0,
+    );
+    match slope {
+        Some(slope) => eval_units_result(env, slope, &f_value, TokenOp::Div, units),
+        None => Err(MyError::EvalError(format!("diff: not available at {}", x0))),
+    }
+}
+
+const INV_GOLDEN: f64 = 0.618_033_988_749_894_9;
+
+/// Minimum of `f` on [a, b] as (x, f(x)). The best point of a coarse grid is refined by
+/// golden-section search between its neighbors, so that a local minimum is not stuck at.
+pub fn minimize_1d<F>(mut f: F, a: f64, b: f64) -> Option<(f64, f64)>
+where
+    F: FnMut(f64) -> Option<f64>,
+{
+    const GRID: usize = 32;
+    let step = (b - a) / GRID as f64;
+    let mut best = (a, f(a)?);
+    let mut best_index = 0;
+    for i in 1..=GRID {
+        let x = if i == GRID { b } else { a + step * i as f64 };
+        let y = f(x)?;
+        if y < best.1 {
+            best = (x, y);
+            best_index = i;
+        }
+    }
+    let mut lo = a + step * best_index.saturating_sub(1) as f64;
+    let mut hi = if best_index + 1 >= GRID {
+        b
+    } else {
+        a + step * (best_index + 1) as f64
+    };
+    let mut x1 = hi - INV_GOLDEN * (hi - lo);
+    let mut x2 = lo + INV_GOLDEN * (hi - lo);
+    let mut y1 = f(x1)?;
+    let mut y2 = f(x2)?;
+    for _ in 0..200 {
+        if (hi - lo).abs() <= 4.0 * f64::EPSILON * x1.abs().max(x2.abs()).max(f64::MIN_POSITIVE) {
+            break;
+        }
+        if y1 < y2 {
+            hi = x2;
+            x2 = x1;
+            y2 = y1;
+            x1 = hi - INV_GOLDEN * (hi - lo);
+            y1 = f(x1)?;
+        } else {
+            lo = x1;
+            x1 = x2;
+            y1 = y2;
+            x2 = lo + INV_GOLDEN * (hi - lo);
+            y2 = f(x2)?;
+        }
+    }
+    for (x, y) in [(x1, y1), (x2, y2)].iter() {
+        if *y < best.1 {
+            best = (*x, *y);
+        }
+    }
+    Some(best)
+}
+
+/// Minimum of `f` in the box [lower, upper] by Nelder-Mead method, as (x, f(x)).
+/// Points out of the box are clamped to the bounds. It is restarted from the result
+/// until it does not improve, since the simplex may collapse before the minimum.
+pub fn minimize_nd<F>(mut f: F, lower: &[f64], upper: &[f64]) -> Option<(Vec<f64>, f64)>
+where
+    F: FnMut(&[f64]) -> Option<f64>,
+{
+    let dim = lower.len();
+    let clamp = |x: Vec<f64>| -> Vec<f64> {
+        x.iter()
+            .enumerate()
+            .map(|(i, v)| v.max(lower[i].min(upper[i])).min(lower[i].max(upper[i])))
+            .collect()
+    };
+    let mut start: Vec<f64> = (0..dim).map(|i| (lower[i] + upper[i]) / 2.0).collect();
+    let mut scale = 0.25;
+    let mut best = (start.clone(), f(&start)?);
+    for _ in 0..10 {
+        let mut simplex = vec![(start.clone(), f(&start)?)];
+        for i in 0..dim {
+            let mut x = start.clone();
+            let step = (upper[i] - lower[i]) * scale;
+            x[i] = if x[i] + step > lower[i].max(upper[i]) {
+                x[i] - step
+            } else {
+                x[i] + step
+            };
+            let y = f(&x)?;
+            simplex.push((x, y));
+        }
+        for _ in 0..2000 * dim {
+            simplex.sort_by(|p, q| p.1.partial_cmp(&q.1).unwrap_or(Ordering::Equal));
+            let spread = (simplex[dim].1 - simplex[0].1).abs();
+            let size = simplex[1..].iter().fold(0.0f64, |m, p| {
+                p.0.iter()
+                    .zip(simplex[0].0.iter())
+                    .enumerate()
+                    .map(|(i, (a, b))| (a - b).abs() / (upper[i] - lower[i]).abs())
+                    .fold(m, f64::max)
+            });
+            if spread <= 1e-15 * simplex[0].1.abs() && size <= 1e-12 || size <= 1e-15 {
+                break;
+            }
+            let centroid: Vec<f64> = (0..dim)
+                .map(|i| simplex[..dim].iter().map(|p| p.0[i]).sum::<f64>() / dim as f64)
+                .collect();
+            let toward = |t: f64, from: &[f64]| -> Vec<f64> {
+                clamp(
+                    centroid
+                        .iter()
+                        .zip(from.iter())
+                        .map(|(c, w)| c + t * (w - c))
+                        .collect(),
+                )
+            };
+            let worst = simplex[dim].clone();
+            let reflected = toward(-1.0, &worst.0);
+            let y_reflected = f(&reflected)?;
+            if y_reflected < simplex[0].1 {
+                let expanded = toward(-2.0, &worst.0);
+                let y_expanded = f(&expanded)?;
+                simplex[dim] = if y_expanded < y_reflected {
+                    (expanded, y_expanded)
+                } else {
+                    (reflected, y_reflected)
+                };
+            } else if y_reflected < simplex[dim - 1].1 {
+                simplex[dim] = (reflected, y_reflected);
+            } else {
+                let contracted = if y_reflected < worst.1 {
+                    toward(-0.5, &worst.0)
+                } else {
+                    toward(0.5, &worst.0)
+                };
+                let y_contracted = f(&contracted)?;
+                if y_contracted < worst.1.min(y_reflected) {
+                    simplex[dim] = (contracted, y_contracted);
+                } else {
+                    // shrink toward the best point
+                    for k in 1..=dim {
+                        let x: Vec<f64> = simplex[k]
+                            .0
+                            .iter()
+                            .zip(simplex[0].0.iter())
+                            .map(|(x, b)| b + 0.5 * (x - b))
+                            .collect();
+                        let y = f(&x)?;
+                        simplex[k] = (x, y);
+                    }
+                }
+            }
+        }
+        simplex.sort_by(|p, q| p.1.partial_cmp(&q.1).unwrap_or(Ordering::Equal));
+        let improved = simplex[0].1 < best.1;
+        let moved = simplex[0].0 != best.0;
+        if improved {
+            best = simplex[0].clone();
+        }
+        if !improved || !moved {
+            break;
+        }
+        start = best.0.clone();
+        scale = 0.05;
+    }
+    Some(best)
+}
+
+// Names of the variables, `x` or a list of them, i.e. `{x, y}`.
+fn variable_names(env: &Env, n: &Node) -> Result<Vec<String>, MyError> {
+    match n {
+        Node::List(items) => items.iter().map(|item| variable_name(env, item)).collect(),
+        var => Ok(vec![variable_name(env, var)?]),
+    }
+}
+
+// (f, variables, [lower, upper]) of `name(f, a, b)` or `name(expr, x, a, b)`.
+// Variables and bounds are lists for more than one variable.
+fn optimize_args<'a>(
+    name: &str,
+    arg: &'a [Node],
+) -> Result<(&'a Node, Option<&'a Node>, &'a [Node]), MyError> {
+    match arg.len() {
+        3 => Ok((&arg[0], None, &arg[1..])),
+        4 => Ok((&arg[0], Some(&arg[1]), &arg[2..])),
+        _ => Err(MyError::EvalError(format!(
+            "{} requires 3 or 4 parameter(s): {:?}",
+            name, arg
+        ))),
+    }
+}
+
+fn real_values(env: &mut Env, n: &Node) -> Result<Vec<(f64, Box<Node>)>, MyError> {
+    match eval(env, n)? {
+        Node::List(items) => items.iter().map(|item| real_value(env, item)).collect(),
+        value => Ok(vec![real_value(env, &value)?]),
+    }
+}
+
+// Value of `f` at the point. `f` is an expression of `vars`, or a function name or
+// a lambda of `_1`, `_2`, ... when `vars` is `None`.
+fn eval_node_at_point(
+    env: &mut Env,
+    f: &Node,
+    vars: Option<&[String]>,
+    point: &[f64],
+    units: &[Box<Node>],
+) -> Result<Node, MyError> {
+    let values: Vec<Node> = point
+        .iter()
+        .zip(units.iter())
+        .map(|(x, u)| Node::FNum(*x, u.clone()))
+        .collect();
+    let names: Vec<String> = match vars {
+        Some(vars) => vars.to_vec(),
+        None => {
+            if let Node::Var(Token::Ident(id)) = f {
+                if env.is_func(id).is_some() || env.is_user_func(id.clone()).is_some() {
+                    return eval(env, &Node::Func(Token::Ident(id.clone()), values));
+                }
+            }
+            (1..=values.len()).map(|i| format!("_{}", i)).collect()
+        }
+    };
+    let saved: Vec<Option<Node>> = names
+        .iter()
+        .zip(values)
+        .map(|(name, value)| env.variable.insert(name.clone(), value))
+        .collect();
+    let ret = eval(env, f);
+    for (name, value) in names.iter().zip(saved) {
+        match value {
+            Some(v) => env.variable.insert(name.clone(), v),
+            None => env.variable.remove(name),
+        };
+    }
+    ret
+}
+
+fn optimize(env: &mut Env, name: &str, arg: &[Node], sign: f64) -> Result<Node, MyError> {
+    let (f, vars, bounds) = optimize_args(name, arg)?;
+    let vars = match vars {
+        Some(vars) => Some(variable_names(env, vars)?),
+        None => None,
+    };
+    let lower = real_values(env, &bounds[0])?;
+    let upper = real_values(env, &bounds[1])?;
+    let dim = lower.len();
+    if upper.len() != dim || vars.as_ref().is_some_and(|v| v.len() != dim) {
+        return Err(MyError::EvalError(format!(
+            "{}: number of variables and bounds should be the same",
+            name
+        )));
+    }
+    let units: Vec<Box<Node>> = lower.iter().map(|(_, u)| u.clone()).collect();
+    let lower: Vec<f64> = lower.iter().map(|(v, _)| *v).collect();
+    let upper: Vec<f64> = upper.iter().map(|(v, _)| *v).collect();
+    let vars = vars.as_deref();
+    // error of the expression itself is reported
+    let center: Vec<f64> = lower
+        .iter()
+        .zip(upper.iter())
+        .map(|(a, b)| (a + b) / 2.0)
+        .collect();
+    let f_value = eval_node_at_point(env, f, vars, &center, &units)?;
+    eval_fvalue(env, &f_value)?;
+    let mut objective = |x: &[f64]| {
+        eval_node_at_point(env, f, vars, x, &units)
+            .and_then(|y| eval_fvalue(env, &y))
+            .ok()
+            .filter(|y| y.is_finite())
+            .map(|y| sign * y)
+    };
+    let found = if dim == 1 {
+        minimize_1d(|x| objective(&[x]), lower[0], upper[0]).map(|(x, y)| (vec![x], y))
+    } else {
+        minimize_nd(objective, &lower, &upper)
+    };
+    let (point, _) = found
+        .ok_or_else(|| MyError::EvalError(format!("{}: not available in the bounds", name)))?;
+    let value = eval_node_at_point(env, f, vars, &point, &units)?;
+    let mut ret: Vec<Node> = point
+        .iter()
+        .zip(units)
+        .map(|(x, u)| Node::FNum(*x, u))
+        .collect();
+    ret.push(value);
+    Ok(Node::List(ret))
+}
+
+/// minimize(expr, x, a, b) or minimize(f, a, b): `{x, value}` of the minimum on [a, b].
+/// minimize(expr, {x, y}, {ax, ay}, {bx, by}): `{x, y, value}` of the minimum in the box.
+pub fn impl_minimize(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    optimize(env, "minimize", arg, 1.0)
+}
+
+/// maximize(expr, x, a, b): same as `minimize()` for the maximum.
+pub fn impl_maximize(env: &mut Env, arg: &[Node]) -> Result<Node, MyError> {
+    optimize(env, "maximize", arg, -1.0)
+}
+
+// Dormand-Prince 5(4), coefficients of the Butcher tableau
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+    ],
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+// 5th order weights are the last row of DP_A, the 4th order weights are for the error
+const DP_B4: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+// One step of Dormand-Prince from (t, y) by h, (y(t + h), error estimate).
+fn dormand_prince<F>(f: &mut F, t: f64, y: &[f64], h: f64) -> Option<(Vec<f64>, Vec<f64>)>
+where
+    F: FnMut(f64, &[f64]) -> Option<Vec<f64>>,
+{
+    let mut k: Vec<Vec<f64>> = Vec::new();
+    for stage in 0..7 {
+        let y_stage: Vec<f64> = (0..y.len())
+            .map(|i| y[i] + h * (0..stage).map(|j| DP_A[stage][j] * k[j][i]).sum::<f64>())
+            .collect();
+        let dy = f(t + DP_C[stage] * h, &y_stage)?;
+        if dy.len() != y.len() || dy.iter().any(|v| !v.is_finite()) {
+            return None;
+        }
+        k.push(dy);
+    }
+    let y_next: Vec<f64> = (0..y.len())
+        .map(|i| y[i] + h * (0..6).map(|j| DP_A[6][j] * k[j][i]).sum::<f64>())
+        .collect();
+    let error: Vec<f64> = (0..y.len())
+        .map(|i| {
+            let y4 = y[i] + h * (0..7).map(|j| DP_B4[j] * k[j][i]).sum::<f64>();
+            y_next[i] - y4
+        })
+        .collect();
+    Some((y_next, error))
+}
+
+/// Solution of dy/dt = f(t, y), y(t0) = y0 at `points` + 1 equally spaced times on [t0, t1].
+/// Adaptive Runge-Kutta (Dormand-Prince 5(4)), the step is shrunk to land on the output times.
+pub fn ode_solve<F>(
+    mut f: F,
+    t0: f64,
+    y0: &[f64],
+    t1: f64,
+    points: usize,
+) -> Option<Vec<(f64, Vec<f64>)>>
+where
+    F: FnMut(f64, &[f64]) -> Option<Vec<f64>>,
+{
+    const TOLERANCE: f64 = 1e-10;
+    let span = t1 - t0;
+    let mut ret = vec![(t0, y0.to_vec())];
+    let mut t = t0;
+    let mut y = y0.to_vec();
+    let mut h = span / points as f64 / 4.0;
+    let mut peak = y0.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    let mut steps = 0;
+    for point in 1..=points {
+        let t_out = if point == points {
+            t1
+        } else {
+            t0 + span * point as f64 / points as f64
+        };
+        while (t_out - t) * span.signum() > 0.0 {
+            steps += 1;
+            if steps > 1_000_000 {
+                return None;
+            }
+            let last = (t_out - t).abs() <= h.abs() * 1.000_001;
+            let step = if last { t_out - t } else { h };
+            let (y_next, error) = dormand_prince(&mut f, t, &y, step)?;
+            let scale = y_next
+                .iter()
+                .zip(y.iter())
+                .fold(peak, |m, (a, b)| m.max(a.abs()).max(b.abs()));
+            let ratio = error.iter().fold(0.0f64, |m, e| m.max(e.abs())) / (TOLERANCE * scale);
+            let ratio = if ratio.is_nan() { 0.0 } else { ratio };
+            if ratio <= 1.0 {
+                t = if last { t_out } else { t + step };
+                y = y_next;
+                peak = scale;
+            }
+            let factor = if ratio == 0.0 {
+                5.0
+            } else {
+                (0.9 * ratio.powf(-0.2)).clamp(0.2, 5.0)
+            };
+            h = step * factor;
+            if h.abs() <= f64::EPSILON * t.abs() {
+                return None;
+            }
+        }
+        ret.push((t, y.clone()));
+    }
+    Some(ret)
+}
+
+// Split the tokens at top level commas, i.e. `f, {1, 0}, 0, 1` to `f`, `{1, 0}`, `0` and `1`.
+fn split_args(arg: &[Token]) -> Vec<&[Token]> {
+    let mut ret = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, token) in arg.iter().enumerate() {
+        match token {
+            Token::Op(TokenOp::ParenLeft) | Token::Op(TokenOp::BraceLeft) => depth += 1,
+            Token::Op(TokenOp::ParenRight) | Token::Op(TokenOp::BraceRight) => depth -= 1,
+            Token::Op(TokenOp::Comma) if depth == 0 => {
+                ret.push(&arg[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ret.push(&arg[start..]);
+    ret
+}
+
+// `f` or `{f1, f2}`, an element is a function name or a lambda of `_1`, `_2`, ...
+fn ode_functions(env: &mut Env, tokens: &[Token]) -> Result<Vec<Node>, MyError> {
+    // the first '{' is closed by the last token
+    let mut depth = 0;
+    let closed = tokens.iter().position(|token| {
+        match token {
+            Token::Op(TokenOp::BraceLeft) => depth += 1,
+            Token::Op(TokenOp::BraceRight) => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+    });
+    let is_list = matches!(tokens.first(), Some(Token::Op(TokenOp::BraceLeft)))
+        && closed == Some(tokens.len() - 1);
+    let items = if is_list {
+        split_args(&tokens[1..tokens.len() - 1])
+    } else {
+        vec![tokens]
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            [Token::Ident(id)]
+                if env.is_func(id).is_some() || env.is_user_func(id.clone()).is_some() =>
+            {
+                Ok(Node::Var(Token::Ident(id.clone())))
+            }
+            _ => parse(env, item),
+        })
+        .collect()
+}
+
+fn ode_table(env: &mut Env, arg: &[Token]) -> Result<Vec<Vec<Node>>, MyError> {
+    let args = split_args(arg);
+    if args.len() != 4 && args.len() != 5 {
+        return Err(MyError::EvalError(
+            "ode should be: ode f, y0, t0, t1[, points]".to_owned(),
+        ));
+    }
+    let f = ode_functions(env, args[0])?;
+    let args = args[1..]
+        .iter()
+        .map(|tokens| parse(env, tokens))
+        .collect::<Result<Vec<Node>, MyError>>()?;
+    let y0 = real_values(env, &args[0])?;
+    if y0.len() != f.len() {
+        return Err(MyError::EvalError(
+            "ode: number of functions and initial values should be the same".to_owned(),
+        ));
+    }
+    let (t0, t_units) = real_value(env, &args[1])?;
+    let (t1, _) = real_value(env, &args[2])?;
+    let points = match args.get(3) {
+        Some(n) => match eval(env, n)? {
+            Node::Num(n, _) if n > 0 => n as usize,
+            _ => {
+                return Err(MyError::EvalError(
+                    "ode: points should be positive integer".to_owned(),
+                ))
+            }
+        },
+        None => 10,
+    };
+    let mut units = vec![t_units.clone()];
+    units.extend(y0.iter().map(|(_, u)| u.clone()));
+    let y0: Vec<f64> = y0.iter().map(|(v, _)| *v).collect();
+    let mut point = vec![t0];
+    point.extend(y0.iter());
+    // error of the functions itself is reported
+    for f in f.iter() {
+        let dy = eval_node_at_point(env, f, None, &point, &units)?;
+        eval_fvalue(env, &dy)?;
+    }
+    let solution = ode_solve(
+        |t, y| {
+            let mut point = vec![t];
+            point.extend(y.iter());
+            f.iter()
+                .map(|f| {
+                    eval_node_at_point(env, f, None, &point, &units)
+                        .and_then(|dy| eval_fvalue(env, &dy))
+                        .ok()
+                })
+                .collect()
+        },
+        t0,
+        &y0,
+        t1,
+        points,
+    )
+    .ok_or_else(|| MyError::EvalError(format!("ode: not converged on [{}, {}]", t0, t1)))?;
+    Ok(solution
+        .into_iter()
+        .map(|(t, y)| {
+            let mut row = vec![Node::FNum(t, t_units.clone())];
+            row.extend(
+                y.iter()
+                    .zip(units[1..].iter())
+                    .map(|(v, u)| Node::FNum(*v, u.clone())),
+            );
+            row
+        })
+        .collect())
+}
+
+/// ode f, y0, t0, t1[, points]: solution of dy/dt = f(t, y) as a table of t and y.
+/// For a system, `f` and `y0` are lists, each function is called as f(t, y1, y2, ...).
+/// The table is also stored to `ans` as a matrix.
+pub fn impl_ode(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_ode {:?}\r", arg);
+    }
+    let rows = match ode_table(env, arg) {
+        Ok(rows) => rows,
+        Err(e) => return format!("{}", e),
+    };
+    let mut table: Vec<Vec<String>> = Vec::new();
+    let mut header = vec!["t".to_owned()];
+    header.extend((1..rows[0].len()).map(|i| format!("y{}", i)));
+    table.push(header);
+    for row in rows.iter() {
+        table.push(row.iter().map(|v| output_format(env, v)).collect());
+    }
+    let widths: Vec<usize> = (0..table[0].len())
+        .map(|i| {
+            table
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut ret = String::new();
+    for row in table.iter() {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect();
+        ret.push_str(&cells.join("  "));
+        ret.push_str("\r\n");
+    }
+    env.variable.insert("ans".to_owned(), Node::Matrix(rows));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_root() {
+        let root = find_root(|x| Some(x * x * x - 2.0 * x - 5.0), 2.0).unwrap();
+        assert!((root - 2.0945514815423265).abs() < 1e-12);
+
+        // nearest root
+        let root = find_root(|x| Some((x - 1.0) * (x - 5.0)), 4.0).unwrap();
+        assert!((root - 5.0).abs() < 1e-12);
+
+        // double root without sign change
+        let root = find_root(|x| Some((x - 1.0) * (x - 1.0)), 3.0).unwrap();
+        assert!((root - 1.0).abs() < 1e-6);
+
+        // small scale
+        let root = find_root(|x| Some(x - 2.2e-9), 1e-9).unwrap();
+        assert!((root - 2.2e-9).abs() < 1e-20);
+
+        // not defined for x < 0
+        let root = find_root(|x| Some(x.sqrt() - 2.0).filter(|y| y.is_finite()), 1.0).unwrap();
+        assert!((root - 4.0).abs() < 1e-12);
+
+        assert!(find_root(|x| Some(x * x + 1.0), 1.0).is_none());
+        assert!(find_root(|x| Some(1.0 / x), 1.0).is_none());
+    }
+
+    #[test]
+    fn test_integrate() {
+        let integral = integrate(|x| Some(x * x), 0.0, 3.0).unwrap();
+        assert!((integral - 9.0).abs() < 1e-12);
+        let integral = integrate(|x| Some(x.sin()), 0.0, std::f64::consts::PI).unwrap();
+        assert!((integral - 2.0).abs() < 1e-12);
+        // singular at the end
+        let integral = integrate(|x| Some(1.0 / x.sqrt()), 0.0, 1.0).unwrap();
+        assert!((integral - 2.0).abs() < 1e-8);
+        // reversed and symmetric
+        let integral = integrate(|x| Some(x * x), 1.0, 0.0).unwrap();
+        assert!((integral + 1.0 / 3.0).abs() < 1e-12);
+        let integral = integrate(|x| Some(x * x * x), -1.0, 1.0).unwrap();
+        assert!(integral.abs() < 1e-12);
+        assert!(integrate(|x| Some(1.0 / x).filter(|y| y.is_finite()), 0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_derivative() {
+        let slope = derivative(|x| Some(x * x * x), 2.0).unwrap();
+        assert!((slope - 12.0).abs() < 1e-9);
+        let slope = derivative(|x| Some(x.exp()), 0.0).unwrap();
+        assert!((slope - 1.0).abs() < 1e-10);
+        let slope = derivative(|x| Some(x.sin()), 1e-3).unwrap();
+        assert!((slope - 1e-3f64.cos()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_minimize() {
+        let (x, y) = minimize_1d(|x| Some((x - 2.0) * (x - 2.0) + 1.0), 0.0, 5.0).unwrap();
+        assert!((x - 2.0).abs() < 1e-7);
+        assert!((y - 1.0).abs() < 1e-14);
+        // global minimum of the two local minima
+        let (x, _) = minimize_1d(|x| Some(x.powi(4) - 3.0 * x * x + x), -3.0, 3.0).unwrap();
+        assert!((x + 1.300_839_565_9).abs() < 1e-6);
+        // at the bound
+        let (x, _) = minimize_1d(Some, 1.0, 2.0).unwrap();
+        assert_eq!(x, 1.0);
+
+        // Rosenbrock
+        let (x, y) = minimize_nd(
+            |p| Some((1.0 - p[0]).powi(2) + 100.0 * (p[1] - p[0] * p[0]).powi(2)),
+            &[-2.0, -2.0],
+            &[2.0, 2.0],
+        )
+        .unwrap();
+        assert!((x[0] - 1.0).abs() < 1e-5 && (x[1] - 1.0).abs() < 1e-5);
+        assert!(y < 1e-10);
+        // bounded
+        let (x, _) = minimize_nd(|p| Some(p[0] + p[1]), &[1.0, 2.0], &[3.0, 4.0]).unwrap();
+        assert!((x[0] - 1.0).abs() < 1e-9 && (x[1] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ode_solve() {
+        // RC charging, tau = 1ms
+        let solution = ode_solve(
+            |_t, y| Some(vec![(5.0 - y[0]) / 1e-3]),
+            0.0,
+            &[0.0],
+            5e-3,
+            5,
+        )
+        .unwrap();
+        assert_eq!(solution.len(), 6);
+        for (t, y) in solution.iter() {
+            assert!((y[0] - 5.0 * (1.0 - (-t / 1e-3).exp())).abs() < 1e-8);
+        }
+        assert_eq!(solution[5].0, 5e-3);
+        // harmonic oscillator
+        let solution =
+            ode_solve(|_t, y| Some(vec![y[1], -y[0]]), 0.0, &[0.0, 1.0], 10.0, 4).unwrap();
+        let (t, y) = &solution[4];
+        assert!((y[0] - t.sin()).abs() < 1e-8 && (y[1] - t.cos()).abs() < 1e-8);
+        // backward
+        let solution = ode_solve(|t, _y| Some(vec![2.0 * t]), 2.0, &[4.0], 0.0, 2).unwrap();
+        assert!(solution[2].1[0].abs() < 1e-12);
+        // blow up
+        assert!(ode_solve(|_t, y| Some(vec![y[0] * y[0]]), 0.0, &[1.0], 2.0, 2).is_none());
+    }
+
+    #[test]
+    fn test_ode() {
+        let mut env = Env::new();
+        env.built_in();
+        let table = impl_ode(
+            &mut env,
+            &lexer("(1-_2)/1m, 0, 0, 3m, 3".to_owned()).unwrap(),
+        );
+        assert!(table.starts_with(
+            "    t                  y1\r\n    0                   0\r\n0.001  0.63212055882"
+        ));
+        assert_eq!(table.lines().count(), 5);
+        // ans is the table
+        let value = |env: &Env, row: usize, col: usize| match &env.variable["ans"] {
+            Node::Matrix(rows) => eval_fvalue(env, &rows[row][col]).unwrap(),
+            _ => panic!("ans should be a matrix"),
+        };
+        assert!((value(&env, 3, 1) - (1.0 - (-3f64).exp())).abs() < 1e-10);
+
+        // user function f(t, y)
+        env.user_func
+            .insert("g".to_owned(), lexer("_2".to_owned()).unwrap());
+        impl_ode(&mut env, &lexer("g, 1, 0, 1, 1".to_owned()).unwrap());
+        assert!((value(&env, 1, 1) - std::f64::consts::E).abs() < 1e-8);
+
+        // system of lambdas of t = _1, x = _2 and v = _3
+        impl_ode(
+            &mut env,
+            &lexer("{_3, -_2}, {0, 1}, 0, pi/2, 2".to_owned()).unwrap(),
+        );
+        assert!((value(&env, 2, 1) - 1.0).abs() < 1e-10);
+        assert!(value(&env, 2, 2).abs() < 1e-10);
+
+        let mut ode = |input: &str| impl_ode(&mut env, &lexer(input.to_owned()).unwrap());
+        assert_eq!(
+            ode("{_3, -_2}, 0, 0, 1"),
+            "eval error: ode: number of functions and initial values should be the same".to_owned()
+        );
+        assert_eq!(
+            ode("_2, 0, 1"),
+            "eval error: ode should be: ode f, y0, t0, t1[, points]".to_owned()
+        );
+        assert_eq!(
+            ode("_2^2, 1, 0, 2"),
+            "eval error: ode: not converged on [0, 2]".to_owned()
+        );
+    }
+}
@@ -4,9 +4,10 @@ use std::collections::HashMap;
 // <assign>  ::= <var> '=' <expr>
 // <expr>    ::= <mul> ( '+' <mul> | '-' <mul> )*
 // <mul>     ::= <exp> ( '*' <exp> | '/' <exp>)*
-// <exp>     ::= <unary> '^' <exp> | <unary>
+// <exp>     ::= <unary> '^' <exp> | <unary> '∠' <unary> | <unary>
 // <unary>   ::= <primary> | '-' <primary> | '+' <primary>
-// <primary> ::= <num> | '(' <expr> ')' | <var> | <func> '(' <expr>* ',' ')'
+// <primary> ::= <num> | '(' <expr> ')' | <var> | <func> '(' <expr>* ',' ')' | <list>
+// <list>    ::= '{' <expr>* ',' '}'
 // <num>     ::= <num> | <num> <postfix> | <num> <units> | <num> <postfix> <units>
 // <units>   ::= '[' <expr> ']'
 
@@ -23,6 +24,9 @@ pub enum Node {
     Var(Token),                         // Token::Ident
     Func(Token, Vec<Node>),             // Token::Ident, args...
     Command(Token, Vec<Token>, String), // Token::Ident, args..., result-holder
+    List(Vec<Node>),                    // {1, 2, 3}, operators are applied element by element
+    Matrix(Vec<Vec<Node>>),             // {{1, 2}, {3, 4}}, rows of the same length
+    TransferFn(Vec<Node>, Vec<Node>),   // tf({1}, {1m, 1}), numerator and denominator of s
 }
 
 fn tok_check_index(tok: &[Token], i: usize) -> Result<(), MyError> {
@@ -48,16 +52,24 @@ fn units(env: &mut Env, tok: &[Token], index: usize) -> Result<(Node, usize), My
             Token::Op(TokenOp::SqBracketLeft) => {
                 i += 2;
                 let mut node = Node::None;
+                let parse_units = env.parse_units;
+                env.parse_units = true;
                 loop {
                     match &tok[i] {
                         Token::Op(TokenOp::SqBracketRight) => {
+                            env.parse_units = parse_units;
                             return Ok((Node::Units(Box::new(node)), i));
                         }
-                        _ => {
-                            let (new_node, new_index) = expr(env, tok, i)?;
-                            i = new_index;
-                            node = new_node;
-                        }
+                        _ => match expr(env, tok, i) {
+                            Ok((new_node, new_index)) => {
+                                i = new_index;
+                                node = new_node;
+                            }
+                            Err(e) => {
+                                env.parse_units = parse_units;
+                                return Err(e);
+                            }
+                        },
                     }
                 }
             }
@@ -191,6 +203,9 @@ fn func(
             } else if tok[i] == Token::Op(TokenOp::Comma) {
                 i += 1;
                 continue;
+            } else if let Some(name) = function_name_arg(env, id, tok, i) {
+                i += 1;
+                params.push(name);
             } else if let Ok((t, j)) = expr(env, tok, i) {
                 i = j;
                 params.push(t);
@@ -214,6 +229,71 @@ fn func(
     )))
 }
 
+// A function name as an argument of a lazy function is a name, i.e. `f` of map(f, v).
+fn function_name_arg(env: &Env, id: &str, tok: &[Token], i: usize) -> Option<Node> {
+    env.is_lazy_func(id)?;
+    let name = match &tok[i] {
+        Token::Ident(name) => name,
+        _ => return None,
+    };
+    if env.is_func(name).is_none() && env.is_user_func(name.clone()).is_none() {
+        return None;
+    }
+    match tok.get(i + 1) {
+        Some(Token::Op(TokenOp::Comma)) | Some(Token::Op(TokenOp::ParenRight)) => {
+            Some(Node::Var(Token::Ident(name.clone())))
+        }
+        _ => None,
+    }
+}
+
+// A list of lists of the same length is a matrix.
+fn list_or_matrix(items: Vec<Node>) -> Node {
+    let rows: Vec<&Vec<Node>> = items
+        .iter()
+        .filter_map(|item| match item {
+            Node::List(row) => Some(row),
+            _ => None,
+        })
+        .collect();
+    if items.is_empty()
+        || rows.len() != items.len()
+        || rows[0].is_empty()
+        || rows.iter().any(|row| row.len() != rows[0].len())
+    {
+        return Node::List(items);
+    }
+    Node::Matrix(rows.into_iter().cloned().collect())
+}
+
+fn list(env: &mut Env, tok: &[Token], index: usize) -> Result<(Node, usize), MyError> {
+    let mut i = index + 1;
+    let mut items = Vec::new();
+    while i < tok.len() {
+        if tok[i] == Token::Op(TokenOp::BraceRight) {
+            return Ok((list_or_matrix(items), i + 1));
+        }
+        let (item, j) = expr(env, tok, i)?;
+        items.push(item);
+        i = j;
+        tok_check_index(tok, i)?;
+        match tok[i] {
+            Token::Op(TokenOp::Comma) => i += 1,
+            Token::Op(TokenOp::BraceRight) => {}
+            _ => {
+                return Err(MyError::ParseError(format!(
+                    "list expects ',' or '}}': {:?} {}",
+                    tok, i
+                )));
+            }
+        }
+    }
+    Err(MyError::ParseError(format!(
+        "list has no '}}': {:?} {}",
+        tok, i
+    )))
+}
+
 fn cmd(_env: &Env, id: &str, tok: &[Token], index: usize) -> Result<(Node, usize), MyError> {
     let mut i = index;
     let mut params = Vec::new();
@@ -249,11 +329,18 @@ fn primary(env: &mut Env, tok: &[Token], index: usize) -> Result<(Node, usize),
                 Ok((ex, i + 1))
             }
         }
+        Token::Op(TokenOp::BraceLeft) => list(env, tok, i),
         Token::Ident(id) => {
             if let Some(_constant) = env.is_const(id.as_str()) {
                 Ok((Node::Var(Token::Ident(id.clone())), i + 1))
             } else if let Some(func_tuple) = env.is_func(id.as_str()) {
+                if env.parse_units && tok.get(i + 1) != Some(&Token::Op(TokenOp::ParenLeft)) {
+                    // function name without '(' in units is a unit, i.e. `[deg]`.
+                    return Ok((Node::Var(Token::Ident(id.clone())), i + 1));
+                }
                 func(env, id, func_tuple.1, tok, index)
+            } else if let Some(lazy_func_tuple) = env.is_lazy_func(id.as_str()) {
+                func(env, id, lazy_func_tuple.1, tok, index)
             } else if let Some(_tokens) = env.is_user_func((*id).clone()) {
                 func(env, &(*id).to_owned(), 0, tok, index)
             } else if let Some(_cmd_tuple) = env.is_cmd(id.as_str()) {
@@ -302,6 +389,13 @@ fn exp(env: &mut Env, tok: &[Token], i: usize) -> Result<(Node, usize), MyError>
             Node::BinOp(Token::Op(TokenOp::Caret), Box::new(lhs), Box::new(rhs)),
             i,
         ))
+    } else if tok[i] == Token::Op(TokenOp::Angle) {
+        let (rhs, j) = unary(env, tok, i + 1)?;
+        i = j;
+        Ok((
+            Node::BinOp(Token::Op(TokenOp::Angle), Box::new(lhs), Box::new(rhs)),
+            i,
+        ))
     } else {
         Ok((lhs, i))
     }
@@ -496,6 +590,15 @@ mod tests {
             "BinOp(Op(Plus), Num(1, Units(None)), CNum(Complex { re: 0.0, im: 2.0 }, Units(None)))"
         );
         assert_eq!(parse_as_string(&mut env, "i"), "Var(Ident(\"i\"))");
+        assert_eq!(
+            parse_as_string(&mut env, "2*10∠-45"),
+            "BinOp(Op(Mul), Num(2, Units(None)), BinOp(Op(Angle), Num(10, Units(None)), Unary(Op(Minus), Num(45, Units(None)))))"
+        );
+        assert_eq!(
+            parse_as_string(&mut env, "{1, 2+3}*2"),
+            "BinOp(Op(Mul), List([Num(1, Units(None)), BinOp(Op(Plus), Num(2, Units(None)), Num(3, Units(None)))]), Num(2, Units(None)))"
+        );
+        assert_eq!(parse_as_string(&mut env, "{}"), "List([])");
         // 新しいテストケース
         assert_eq!(
             parse_as_string(&mut env, "3+4*2/(1-5)^2^3"),
@@ -586,6 +689,12 @@ mod tests {
         if parse(&mut env, &(lexer("sin".to_owned())).unwrap()).is_ok() {
             panic!("error");
         }
+        if parse(&mut env, &(lexer("{1, 2".to_owned())).unwrap()).is_ok() {
+            panic!("parse of unclosed list should return error.");
+        }
+        if parse(&mut env, &(lexer("{1 2}".to_owned())).unwrap()).is_ok() {
+            panic!("parse of list without comma should return error.");
+        }
         if parse(&mut env, &(lexer("((())".to_owned())).unwrap()).is_ok() {
             panic!("error");
         }
@@ -190,32 +190,15 @@ where
     W: Write,
 {
     match node {
-        Node::Num(n, units) => {
+        Node::Num(_, _)
+        | Node::FNum(_, _)
+        | Node::CNum(_, _)
+        | Node::List(_)
+        | Node::Matrix(_)
+        | Node::TransferFn(_, _) => {
             result_print(
                 output,
-                format!(
-                    "{} {}\r\n",
-                    output_format_num(env, n),
-                    output_format_units(env, *units)
-                )
-                .as_str(),
-            );
-        }
-        Node::FNum(f, units) => {
-            result_print(
-                output,
-                format!(
-                    "{} {}\r\n",
-                    output_format_float(env, f),
-                    output_format_units(env, *units)
-                )
-                .as_str(),
-            );
-        }
-        Node::CNum(c, units) => {
-            result_print(
-                output,
-                format!("{} {}\r\n", c, output_format_units(env, *units)).as_str(),
+                format!("{}\r\n", output_format(env, &node)).as_str(),
             );
         }
         Node::Command(cmd, params, result) => {
@@ -238,7 +221,7 @@ fn do_line<W>(output: &mut W, env: &mut Env, line: &str)
 where
     W: Write,
 {
-    match lexer(line.to_owned()) {
+    match lexer_with(line.to_owned(), env.leading_zero_octal) {
         Ok(v) => {
             if v.is_empty() {
                 return;
@@ -318,15 +301,13 @@ pub fn readline(env: &mut Env) {
                         redraw(&mut stdout, "rc> ", &line, prev_cur_x, cur_x);
                     }
                 }
-                KeyCode::Up => {
-                    if env.history_index > 0 {
-                        env.history_index -= 1;
-                        if env.history_index < env.history.len() {
-                            prev_cur_x = line.len() as u16;
-                            line = env.history[env.history_index].clone();
-                            cur_x = line.len() as u16;
-                            redraw(&mut stdout, "rc> ", &line, prev_cur_x, cur_x);
-                        }
+                KeyCode::Up if env.history_index > 0 => {
+                    env.history_index -= 1;
+                    if env.history_index < env.history.len() {
+                        prev_cur_x = line.len() as u16;
+                        line = env.history[env.history_index].clone();
+                        cur_x = line.len() as u16;
+                        redraw(&mut stdout, "rc> ", &line, prev_cur_x, cur_x);
                     }
                 }
                 KeyCode::Down => {
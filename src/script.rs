@@ -9,15 +9,18 @@ pub fn do_script(env: &mut Env, line: &str) -> Result<String, MyError> {
     if env.debug {
         eprint!("{}", line);
     }
-    let tokens = lexer(line.to_owned())?;
+    let tokens = lexer_with(line.to_owned(), env.leading_zero_octal)?;
     if tokens.is_empty() {
         return Ok("".to_owned());
     }
     let node = parse(env, &tokens)?;
     match eval_top(env, &node)? {
-        Node::Num(n, _) => Ok(output_format_num(env, n)),
-        Node::FNum(f, _) => Ok(format!("{}", f)),
-        Node::CNum(c, _) => Ok(format!("{}", c)),
+        result @ (Node::Num(_, _)
+        | Node::FNum(_, _)
+        | Node::CNum(_, _)
+        | Node::List(_)
+        | Node::Matrix(_)
+        | Node::TransferFn(_, _)) => Ok(output_format(env, &result)),
         // Node::Command(_cmd, _params, result) => Ok(format!("{}\r\n", result)),
         Node::Command(_cmd, _params, _result) => Ok("".to_owned()),
         Node::None => Ok("".to_owned()),
@@ -0,0 +1,651 @@
+use super::*;
+
+fn op(n: &Node) -> Option<&TokenOp> {
+    match n {
+        Node::BinOp(Token::Op(op), _, _) => Some(op),
+        _ => None,
+    }
+}
+
+// precedence of the parser: expr < mul < exp < unary/primary
+fn precedence(n: &Node) -> u8 {
+    match op(n) {
+        Some(TokenOp::Equal) => 0,
+        Some(TokenOp::Plus) | Some(TokenOp::Minus) => 1,
+        Some(TokenOp::Mul) | Some(TokenOp::Div) | Some(TokenOp::Mod) | Some(TokenOp::Para) => 2,
+        Some(_) => 3,
+        // unary minus and negative numbers are primary
+        None => 4,
+    }
+}
+
+fn op_string(op: &TokenOp) -> &'static str {
+    match op {
+        TokenOp::Plus => " + ",
+        TokenOp::Minus => " - ",
+        TokenOp::Mul => "*",
+        TokenOp::Div => "/",
+        TokenOp::Mod => "%",
+        TokenOp::Para => "//",
+        TokenOp::Caret => "^",
+        TokenOp::Angle => "∠",
+        TokenOp::Equal => " = ",
+        _ => "?",
+    }
+}
+
+fn units_string(units: &Node) -> String {
+    match units {
+        Node::Units(u) if **u != Node::None => format!("[{}]", symbolic_string(u)),
+        _ => String::new(),
+    }
+}
+
+/// Expression as the input of the parser, i.e. `3*x^2 + cos(x)`.
+pub fn symbolic_string(n: &Node) -> String {
+    match n {
+        Node::Num(n, units) => format!("{}{}", n, units_string(units)),
+        Node::FNum(f, units) => format!("{}{}", f, units_string(units)),
+        Node::CNum(c, units) => format!("({}+{}*i){}", c.re, c.im, units_string(units)),
+        Node::Var(Token::Ident(id)) => id.clone(),
+        Node::Func(Token::Ident(id), args) => {
+            let args: Vec<String> = args.iter().map(symbolic_string).collect();
+            format!("{}({})", id, args.join(", "))
+        }
+        Node::Unary(Token::Op(TokenOp::Minus), operand) => {
+            // unary operator is applied to primary, -x^2 is (-x)^2
+            let s = symbolic_string(operand);
+            if precedence(operand) < 4 || s.starts_with('-') {
+                format!("-({})", s)
+            } else {
+                format!("-{}", s)
+            }
+        }
+        Node::Unary(_, operand) => symbolic_string(operand),
+        Node::BinOp(Token::Op(op), lhs, rhs) => {
+            let prec = precedence(n);
+            let right_assoc = *op == TokenOp::Caret;
+            let lhs_paren = if right_assoc {
+                precedence(lhs) <= prec
+            } else {
+                precedence(lhs) < prec
+            };
+            let rhs_paren = if right_assoc {
+                precedence(rhs) < prec
+            } else {
+                precedence(rhs) < prec
+                    || (precedence(rhs) == prec && *op != TokenOp::Plus && *op != TokenOp::Mul)
+            };
+            let paren = |s: String, p: bool| if p { format!("({})", s) } else { s };
+            format!(
+                "{}{}{}",
+                paren(symbolic_string(lhs), lhs_paren),
+                op_string(op),
+                paren(symbolic_string(rhs), rhs_paren)
+            )
+        }
+        Node::List(items) => {
+            let items: Vec<String> = items.iter().map(symbolic_string).collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        _ => format!("{:?}", n),
+    }
+}
+
+fn num(n: i128) -> Node {
+    Node::Num(n, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+fn fnum(f: f64) -> Node {
+    Node::FNum(f, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+fn binop(op: TokenOp, lhs: Node, rhs: Node) -> Node {
+    Node::BinOp(Token::Op(op), Box::new(lhs), Box::new(rhs))
+}
+
+fn neg(n: Node) -> Node {
+    Node::Unary(Token::Op(TokenOp::Minus), Box::new(n))
+}
+
+fn func(name: &str, arg: Node) -> Node {
+    Node::Func(Token::Ident(name.to_owned()), vec![arg])
+}
+
+fn is_unitless(units: &Node) -> bool {
+    matches!(units, Node::Units(u) if **u == Node::None)
+}
+
+// a number without units
+fn number(n: &Node) -> Option<f64> {
+    match n {
+        Node::Num(n, units) if is_unitless(units) => Some(*n as f64),
+        Node::FNum(f, units) if is_unitless(units) => Some(*f),
+        _ => None,
+    }
+}
+
+fn is_number(n: &Node, value: f64) -> bool {
+    number(n) == Some(value)
+}
+
+fn depends_on(n: &Node, var: &str) -> bool {
+    match n {
+        Node::Var(Token::Ident(id)) => id == var,
+        Node::Unary(_, operand) => depends_on(operand, var),
+        Node::BinOp(_, lhs, rhs) => depends_on(lhs, var) || depends_on(rhs, var),
+        Node::Func(_, args) | Node::List(args) => args.iter().any(|a| depends_on(a, var)),
+        _ => false,
+    }
+}
+
+// Constant folding, integers are kept as integer when the result is exact.
+fn fold(op: &TokenOp, lhs: &Node, rhs: &Node) -> Option<Node> {
+    let (a, b) = (number(lhs)?, number(rhs)?);
+    if let (Node::Num(a, _), Node::Num(b, _)) = (lhs, rhs) {
+        let (a, b) = (*a, *b);
+        let ret = match op {
+            TokenOp::Plus => a.checked_add(b),
+            TokenOp::Minus => a.checked_sub(b),
+            TokenOp::Mul => a.checked_mul(b),
+            TokenOp::Div => match a.checked_rem(b) {
+                Some(0) => a.checked_div(b),
+                _ => None,
+            },
+            TokenOp::Caret if (0..=127).contains(&b) => a.checked_pow(b as u32),
+            _ => None,
+        };
+        return ret.map(num);
+    }
+    let ret = match op {
+        TokenOp::Plus => a + b,
+        TokenOp::Minus => a - b,
+        TokenOp::Mul => a * b,
+        TokenOp::Div => a / b,
+        TokenOp::Caret => a.powf(b),
+        _ => return None,
+    };
+    if ret.is_finite() {
+        Some(fnum(ret))
+    } else {
+        None
+    }
+}
+
+// 3*x => (3, x), -x => (-1, x), x => (1, x)
+fn split_coefficient(n: &Node) -> (Node, Node) {
+    match n {
+        Node::BinOp(Token::Op(TokenOp::Mul), lhs, rhs) if number(lhs).is_some() => {
+            ((**lhs).clone(), (**rhs).clone())
+        }
+        Node::Unary(Token::Op(TokenOp::Minus), operand) => (num(-1), (**operand).clone()),
+        _ => (num(1), n.clone()),
+    }
+}
+
+// x^3 => (x, 3), x => (x, 1)
+fn split_power(n: &Node) -> (Node, Node) {
+    match n {
+        Node::BinOp(Token::Op(TokenOp::Caret), lhs, rhs) => ((**lhs).clone(), (**rhs).clone()),
+        _ => (n.clone(), num(1)),
+    }
+}
+
+fn simplify_binop(op: &TokenOp, a: Node, b: Node) -> Node {
+    if let Some(folded) = fold(op, &a, &b) {
+        return folded;
+    }
+    match op {
+        TokenOp::Plus | TokenOp::Minus => {
+            let plus = *op == TokenOp::Plus;
+            if is_number(&b, 0.0) {
+                return a;
+            }
+            if is_number(&a, 0.0) {
+                return if plus { b } else { simplify_unary_minus(b) };
+            }
+            // a + -b => a - b
+            if let Node::Unary(Token::Op(TokenOp::Minus), operand) = &b {
+                let op = if plus { TokenOp::Minus } else { TokenOp::Plus };
+                return binop(op, a, (**operand).clone());
+            }
+            if let Some(v) = number(&b) {
+                if v < 0.0 {
+                    let op = if plus { TokenOp::Minus } else { TokenOp::Plus };
+                    return binop(op, a, simplify_unary_minus(b));
+                }
+            }
+            // 2*x + 3*x => 5*x
+            let (ca, ra) = split_coefficient(&a);
+            let (cb, rb) = split_coefficient(&b);
+            if ra == rb {
+                let c = fold(op, &ca, &cb).unwrap_or_else(|| binop(op.clone(), ca, cb));
+                return simplify_binop(&TokenOp::Mul, c, ra);
+            }
+            binop(op.clone(), a, b)
+        }
+        TokenOp::Mul => {
+            if is_number(&a, 0.0) || is_number(&b, 0.0) {
+                return num(0);
+            }
+            if is_number(&a, 1.0) {
+                return b;
+            }
+            if is_number(&b, 1.0) {
+                return a;
+            }
+            if is_number(&a, -1.0) {
+                return simplify_unary_minus(b);
+            }
+            if is_number(&b, -1.0) {
+                return simplify_unary_minus(a);
+            }
+            // coefficient first: x*2 => 2*x, 2*(3*x) => 6*x, (2*x)*y => 2*(x*y)
+            if number(&b).is_some() {
+                if number(&a).is_some() {
+                    // product of numbers which does not fold, i.e. overflow
+                    return binop(TokenOp::Mul, a, b);
+                }
+                return simplify_binop(op, b, a);
+            }
+            if let Node::Unary(Token::Op(TokenOp::Minus), operand) = a {
+                return simplify_unary_minus(simplify_binop(op, *operand, b));
+            }
+            if let Node::Unary(Token::Op(TokenOp::Minus), operand) = b {
+                return simplify_unary_minus(simplify_binop(op, a, *operand));
+            }
+            let (ca, ra) = split_coefficient(&a);
+            let (cb, rb) = split_coefficient(&b);
+            if !is_number(&ca, 1.0) || !is_number(&cb, 1.0) {
+                if number(&a).is_some() {
+                    return match fold(op, &a, &cb) {
+                        Some(c) if !is_number(&cb, 1.0) => simplify_binop(op, c, rb),
+                        _ => binop(TokenOp::Mul, a, b),
+                    };
+                }
+                let c = simplify_binop(op, ca, cb);
+                return simplify_binop(op, c, simplify_binop(op, ra, rb));
+            }
+            // x^2*x => x^3
+            let (base_a, exp_a) = split_power(&a);
+            let (base_b, exp_b) = split_power(&b);
+            if base_a == base_b {
+                return simplify_binop(
+                    &TokenOp::Caret,
+                    base_a,
+                    simplify_binop(&TokenOp::Plus, exp_a, exp_b),
+                );
+            }
+            binop(TokenOp::Mul, a, b)
+        }
+        TokenOp::Div => {
+            if is_number(&a, 0.0) && !is_number(&b, 0.0) {
+                return num(0);
+            }
+            if is_number(&b, 1.0) {
+                return a;
+            }
+            if is_number(&b, -1.0) {
+                return simplify_unary_minus(a);
+            }
+            if let Node::Unary(Token::Op(TokenOp::Minus), operand) = a {
+                return simplify_unary_minus(simplify_binop(op, *operand, b));
+            }
+            // x^3/x => x^2
+            let (base_a, exp_a) = split_power(&a);
+            let (base_b, exp_b) = split_power(&b);
+            if base_a == base_b && number(&base_a).is_none() {
+                return simplify_binop(
+                    &TokenOp::Caret,
+                    base_a,
+                    simplify_binop(&TokenOp::Minus, exp_a, exp_b),
+                );
+            }
+            binop(TokenOp::Div, a, b)
+        }
+        TokenOp::Caret => {
+            if is_number(&b, 0.0) {
+                return num(1);
+            }
+            if is_number(&b, 1.0) {
+                return a;
+            }
+            if is_number(&a, 1.0) {
+                return num(1);
+            }
+            binop(TokenOp::Caret, a, b)
+        }
+        _ => binop(op.clone(), a, b),
+    }
+}
+
+fn simplify_unary_minus(n: Node) -> Node {
+    match n {
+        Node::Num(v, units) => match v.checked_neg() {
+            Some(v) => Node::Num(v, units),
+            None => neg(Node::Num(v, units)),
+        },
+        Node::FNum(v, units) => Node::FNum(-v, units),
+        Node::Unary(Token::Op(TokenOp::Minus), operand) => *operand,
+        Node::BinOp(Token::Op(TokenOp::Minus), lhs, rhs) => {
+            Node::BinOp(Token::Op(TokenOp::Minus), rhs, lhs)
+        }
+        Node::BinOp(Token::Op(TokenOp::Mul), lhs, rhs) if number(&lhs).is_some() => {
+            simplify_binop(&TokenOp::Mul, simplify_unary_minus(*lhs), *rhs)
+        }
+        n => neg(n),
+    }
+}
+
+fn simplify_func(name: &str, args: Vec<Node>) -> Node {
+    if args.len() == 1 {
+        let arg = &args[0];
+        match name {
+            "sin" | "tan" | "sinh" | "tanh" | "sqrt" | "asin" | "atan" if is_number(arg, 0.0) => {
+                return num(0);
+            }
+            "cos" | "cosh" | "exp" if is_number(arg, 0.0) => return num(1),
+            "ln" if is_number(arg, 1.0) => return num(0),
+            "sqrt" if is_number(arg, 1.0) => return num(1),
+            "ln" | "exp" => {
+                // ln(exp(x)) => x, exp(ln(x)) => x
+                let inverse = if name == "ln" { "exp" } else { "ln" };
+                if let Node::Func(Token::Ident(id), inner) = arg {
+                    if id == inverse && inner.len() == 1 {
+                        return inner[0].clone();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Node::Func(Token::Ident(name.to_owned()), args)
+}
+
+fn simplify_once(n: &Node) -> Node {
+    match n {
+        Node::BinOp(Token::Op(op), lhs, rhs) => {
+            simplify_binop(op, simplify_once(lhs), simplify_once(rhs))
+        }
+        Node::Unary(Token::Op(TokenOp::Minus), operand) => {
+            simplify_unary_minus(simplify_once(operand))
+        }
+        Node::Unary(_, operand) => simplify_once(operand),
+        Node::Func(Token::Ident(name), args) => {
+            simplify_func(name, args.iter().map(simplify_once).collect())
+        }
+        Node::List(items) => Node::List(items.iter().map(simplify_once).collect()),
+        _ => n.clone(),
+    }
+}
+
+/// Constant folding and algebraic identities such as x*1 = x, x - x = 0 and x*x = x^2.
+/// Named constants such as `pi` are kept.
+pub fn simplify(n: &Node) -> Node {
+    let mut n = n.clone();
+    for _ in 0..20 {
+        let next = simplify_once(&n);
+        if next == n {
+            break;
+        }
+        n = next;
+    }
+    n
+}
+
+// d/du f(u)
+fn derive_func(name: &str, u: &Node) -> Result<Node, MyError> {
+    let u = u.clone();
+    let one_over = |n: Node| binop(TokenOp::Div, num(1), n);
+    let sqrt = |n: Node| func("sqrt", n);
+    let square = |n: Node| binop(TokenOp::Caret, n, num(2));
+    Ok(match name {
+        "sin" => func("cos", u),
+        "cos" => neg(func("sin", u)),
+        "tan" => one_over(square(func("cos", u))),
+        "exp" => func("exp", u),
+        "ln" => one_over(u),
+        "log10" => one_over(binop(TokenOp::Mul, u, func("ln", num(10)))),
+        "log2" => one_over(binop(TokenOp::Mul, u, func("ln", num(2)))),
+        "sqrt" => one_over(binop(TokenOp::Mul, num(2), sqrt(u))),
+        "asin" => one_over(sqrt(binop(TokenOp::Minus, num(1), square(u)))),
+        "acos" => neg(one_over(sqrt(binop(TokenOp::Minus, num(1), square(u))))),
+        "atan" => one_over(binop(TokenOp::Plus, num(1), square(u))),
+        "sinh" => func("cosh", u),
+        "cosh" => func("sinh", u),
+        "tanh" => one_over(square(func("cosh", u))),
+        "asinh" => one_over(sqrt(binop(TokenOp::Plus, square(u), num(1)))),
+        "acosh" => one_over(sqrt(binop(TokenOp::Minus, square(u), num(1)))),
+        "atanh" => one_over(binop(TokenOp::Minus, num(1), square(u))),
+        "abs" => func("sign", u),
+        _ => {
+            return Err(MyError::EvalError(format!(
+                "derivative of {}() is not supported",
+                name
+            )))
+        }
+    })
+}
+
+/// Derivative of the expression by `var`, the result is not simplified.
+pub fn derive(n: &Node, var: &str) -> Result<Node, MyError> {
+    match n {
+        Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _) => Ok(num(0)),
+        Node::Var(Token::Ident(id)) => Ok(num(if id == var { 1 } else { 0 })),
+        Node::Unary(Token::Op(TokenOp::Minus), u) => Ok(neg(derive(u, var)?)),
+        Node::Unary(_, u) => derive(u, var),
+        Node::BinOp(Token::Op(op), u, v) => {
+            let (u, v) = ((**u).clone(), (**v).clone());
+            match op {
+                TokenOp::Plus | TokenOp::Minus => {
+                    Ok(binop(op.clone(), derive(&u, var)?, derive(&v, var)?))
+                }
+                TokenOp::Mul => Ok(binop(
+                    TokenOp::Plus,
+                    binop(TokenOp::Mul, derive(&u, var)?, v.clone()),
+                    binop(TokenOp::Mul, u.clone(), derive(&v, var)?),
+                )),
+                TokenOp::Div => Ok(binop(
+                    TokenOp::Div,
+                    binop(
+                        TokenOp::Minus,
+                        binop(TokenOp::Mul, derive(&u, var)?, v.clone()),
+                        binop(TokenOp::Mul, u.clone(), derive(&v, var)?),
+                    ),
+                    binop(TokenOp::Caret, v, num(2)),
+                )),
+                // u//v = u*v/(u+v)
+                TokenOp::Para => derive(
+                    &binop(
+                        TokenOp::Div,
+                        binop(TokenOp::Mul, u.clone(), v.clone()),
+                        binop(TokenOp::Plus, u, v),
+                    ),
+                    var,
+                ),
+                TokenOp::Caret => {
+                    let du = derive(&u, var)?;
+                    let dv = derive(&v, var)?;
+                    let power = binop(TokenOp::Caret, u.clone(), v.clone());
+                    if !depends_on(&v, var) {
+                        // v*u^(v-1)*du
+                        Ok(binop(
+                            TokenOp::Mul,
+                            binop(
+                                TokenOp::Mul,
+                                v.clone(),
+                                binop(TokenOp::Caret, u, binop(TokenOp::Minus, v, num(1))),
+                            ),
+                            du,
+                        ))
+                    } else if !depends_on(&u, var) {
+                        // u^v*ln(u)*dv
+                        Ok(binop(
+                            TokenOp::Mul,
+                            binop(TokenOp::Mul, power, func("ln", u)),
+                            dv,
+                        ))
+                    } else {
+                        // u^v*(dv*ln(u) + v*du/u)
+                        Ok(binop(
+                            TokenOp::Mul,
+                            power,
+                            binop(
+                                TokenOp::Plus,
+                                binop(TokenOp::Mul, dv, func("ln", u.clone())),
+                                binop(TokenOp::Div, binop(TokenOp::Mul, v, du), u),
+                            ),
+                        ))
+                    }
+                }
+                _ => Err(MyError::EvalError(format!(
+                    "derivative of {:?} is not supported",
+                    op
+                ))),
+            }
+        }
+        Node::Func(Token::Ident(name), args) => {
+            if args.len() != 1 {
+                return Err(MyError::EvalError(format!(
+                    "derivative of {}() is not supported",
+                    name
+                )));
+            }
+            // chain rule
+            Ok(binop(
+                TokenOp::Mul,
+                derive_func(name, &args[0])?,
+                derive(&args[0], var)?,
+            ))
+        }
+        Node::List(items) => Ok(Node::List(
+            items
+                .iter()
+                .map(|item| derive(item, var))
+                .collect::<Result<Vec<Node>, MyError>>()?,
+        )),
+        _ => Err(MyError::EvalError(format!(
+            "derivative of {:?} is not supported",
+            n
+        ))),
+    }
+}
+
+/// derive x expr: symbolic derivative of the expression by `x`, simplified.
+pub fn impl_derive(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_derive {:?}\r", arg);
+    }
+    let var = match arg.first() {
+        Some(Token::Ident(var)) if arg.len() > 1 => var.clone(),
+        _ => return "derive should be: derive variable expression".to_owned(),
+    };
+    match parse(env, &arg[1..]).and_then(|n| derive(&n, &var)) {
+        Ok(n) => symbolic_string(&simplify(&n)),
+        Err(e) => format!("derive: {}", e),
+    }
+}
+
+/// simplify expr: constant folding and algebraic identities.
+pub fn impl_simplify(env: &mut Env, arg: &[Token]) -> String {
+    if env.is_debug() {
+        eprintln!("impl_simplify {:?}\r", arg);
+    }
+    if arg.is_empty() {
+        return "simplify should be: simplify expression".to_owned();
+    }
+    match parse(env, arg) {
+        Ok(n) => symbolic_string(&simplify(&n)),
+        Err(e) => format!("simplify: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(env: &mut Env, input: &str) -> Node {
+        parse(env, &(lexer(input.to_owned())).unwrap()).unwrap()
+    }
+
+    fn simplify_str(input: &str) -> String {
+        let mut env = Env::new();
+        env.built_in();
+        let n = parse_str(&mut env, input);
+        symbolic_string(&simplify(&n))
+    }
+
+    fn derive_str(input: &str) -> String {
+        let mut env = Env::new();
+        env.built_in();
+        let n = parse_str(&mut env, input);
+        symbolic_string(&simplify(&derive(&n, "x").unwrap()))
+    }
+
+    #[test]
+    fn test_symbolic_string() {
+        assert_eq!(simplify_str("(a+b)*c"), "(a + b)*c");
+        assert_eq!(simplify_str("a-(b-c)"), "a - (b - c)");
+        assert_eq!(simplify_str("a/(b*c)"), "a/(b*c)");
+        assert_eq!(simplify_str("a^b^c"), "a^b^c");
+        assert_eq!(simplify_str("(a^b)^c"), "(a^b)^c");
+        assert_eq!(simplify_str("-(a+b)"), "-(a + b)");
+        assert_eq!(simplify_str("-(a-b)"), "b - a");
+        assert_eq!(simplify_str("-sin(a)"), "-sin(a)");
+        assert_eq!(simplify_str("2[m]*a"), "2[m]*a");
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(simplify_str("1+2*3"), "7");
+        assert_eq!(simplify_str("7/2"), "7/2");
+        assert_eq!(simplify_str("7.0/2"), "3.5");
+        assert_eq!(simplify_str("x*1+0"), "x");
+        assert_eq!(simplify_str("0*x+y^1"), "y");
+        assert_eq!(simplify_str("x-x"), "0");
+        assert_eq!(simplify_str("x+x"), "2*x");
+        assert_eq!(simplify_str("2*x+3*x"), "5*x");
+        assert_eq!(simplify_str("x*2*3"), "6*x");
+        assert_eq!(simplify_str("x*x*x"), "x^3");
+        assert_eq!(simplify_str("x^3/x"), "x^2");
+        assert_eq!(simplify_str("a+-b"), "a - b");
+        assert_eq!(simplify_str("-(-a)"), "a");
+        assert_eq!(simplify_str("ln(exp(x))"), "x");
+        assert_eq!(simplify_str("cos(0)*pi"), "pi");
+        // numbers which do not fold are kept
+        assert_eq!(
+            simplify_str("10^20*10^20"),
+            "100000000000000000000*100000000000000000000"
+        );
+        assert_eq!(simplify_str("1e300*1e300"), format!("{}*{}", 1e300, 1e300));
+        assert_eq!(
+            simplify_str("-((-2)^127)"),
+            "-(-170141183460469231731687303715884105728)"
+        );
+        assert_eq!(
+            simplify_str("((-2)^127)/(-1)"),
+            "-(-170141183460469231731687303715884105728)"
+        );
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(derive_str("x^3"), "3*x^2");
+        assert_eq!(derive_str("5"), "0");
+        assert_eq!(derive_str("y*x"), "y");
+        assert_eq!(derive_str("sin(x)*x"), "cos(x)*x + sin(x)");
+        assert_eq!(derive_str("exp(2*x)"), "2*exp(2*x)");
+        assert_eq!(derive_str("1/x"), "-1/x^2");
+        assert_eq!(derive_str("ln(x)"), "1/x");
+        assert_eq!(derive_str("2^x"), "2^x*ln(2)");
+        assert_eq!(derive_str("sqrt(x)"), "1/(2*sqrt(x))");
+        assert_eq!(derive_str("cos(x^2)"), "-2*sin(x^2)*x");
+
+        let mut env = Env::new();
+        env.built_in();
+        let n = parse_str(&mut env, "max(x, 1)");
+        assert!(derive(&n, "x").is_err());
+        let n = parse_str(&mut env, "x%2");
+        assert!(derive(&n, "x").is_err());
+    }
+}
@@ -0,0 +1,505 @@
+use super::*;
+use std::f64::consts::PI;
+
+// Transfer function values are computed as complex numbers, coefficients should not have units.
+// Coefficients are from the highest degree of `s`, same as roots().
+type Poly = Vec<Complex64>;
+
+// Degree limit of the power of a transfer function
+const TF_DEGREE_MAX: usize = 100;
+
+fn tf_scalar(c: Complex64) -> Node {
+    let units = Box::new(Node::Units(Box::new(Node::None)));
+    if c.im == 0.0 {
+        Node::FNum(c.re, units)
+    } else {
+        Node::CNum(c, units)
+    }
+}
+
+fn tf_node(num: &[Complex64], den: &[Complex64]) -> Node {
+    Node::TransferFn(
+        num.iter().map(|c| tf_scalar(*c)).collect(),
+        den.iter().map(|c| tf_scalar(*c)).collect(),
+    )
+}
+
+// Leading zeros are removed, the zero polynomial is {0}.
+fn poly_trim(p: &[Complex64]) -> Poly {
+    let zero = Complex64::new(0.0, 0.0);
+    match p.iter().position(|a| *a != zero) {
+        Some(leading) => p[leading..].to_vec(),
+        None => vec![zero],
+    }
+}
+
+fn poly_mul(a: &[Complex64], b: &[Complex64]) -> Poly {
+    let mut ret = vec![Complex64::new(0.0, 0.0); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            ret[i + j] += x * y;
+        }
+    }
+    poly_trim(&ret)
+}
+
+// square-and-multiply
+fn poly_pow(p: &[Complex64], mut exponent: u128) -> Poly {
+    let mut base = p.to_vec();
+    let mut ret = vec![Complex64::new(1.0, 0.0)];
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            ret = poly_mul(&ret, &base);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = poly_mul(&base, &base);
+        }
+    }
+    ret
+}
+
+fn poly_add(a: &[Complex64], b: &[Complex64], sign: f64) -> Poly {
+    let len = a.len().max(b.len());
+    let mut ret = vec![Complex64::new(0.0, 0.0); len];
+    for (i, x) in a.iter().enumerate() {
+        ret[len - a.len() + i] += x;
+    }
+    for (i, y) in b.iter().enumerate() {
+        ret[len - b.len() + i] += y * sign;
+    }
+    poly_trim(&ret)
+}
+
+// A coefficient is a number without units, i.e. not 1[s].
+fn coefficient(env: &Env, n: &Node) -> Option<Complex64> {
+    let units = match n {
+        Node::Num(_, units) | Node::FNum(_, units) | Node::CNum(_, units) => units,
+        _ => return None,
+    };
+    let unitless = match &**units {
+        Node::Units(u) => match &**u {
+            Node::None => true,
+            Node::UnitsFraction(nume, denom) => nume.keys().chain(denom.keys()).all(|k| k == "_"),
+            _ => false,
+        },
+        _ => false,
+    };
+    if unitless {
+        eval_cvalue(env, n).ok()
+    } else {
+        None
+    }
+}
+
+// (numerator, denominator) of a transfer function, a scalar is a constant transfer function.
+fn tf_values(env: &Env, n: &Node) -> Option<(Poly, Poly)> {
+    match n {
+        Node::TransferFn(num, den) => {
+            let num: Option<Poly> = num.iter().map(|c| coefficient(env, c)).collect();
+            let den: Option<Poly> = den.iter().map(|c| coefficient(env, c)).collect();
+            Some((poly_trim(&num?), poly_trim(&den?)))
+        }
+        Node::Num(_, _) | Node::FNum(_, _) | Node::CNum(_, _) => {
+            Some((vec![coefficient(env, n)?], vec![Complex64::new(1.0, 0.0)]))
+        }
+        _ => None,
+    }
+}
+
+// The zero denominator is an error, i.e. tf({0}, {1})^-1.
+fn tf_result(num: &[Complex64], den: &[Complex64]) -> Result<Node, MyError> {
+    if den == [Complex64::new(0.0, 0.0)] {
+        return Err(MyError::EvalError(
+            "transfer function: zero denominator".to_owned(),
+        ));
+    }
+    Ok(tf_node(num, den))
+}
+
+/// Operators of transfer functions: `*` series, `+` parallel, `/` and `^` integer.
+/// Scalars are constant transfer functions. Common factors are not canceled.
+pub fn eval_tf_binop(env: &Env, tok: &Token, lhs: &Node, rhs: &Node) -> Result<Node, MyError> {
+    let error = || MyError::EvalError(format!("transfer function {:?}: {:?} {:?}", tok, lhs, rhs));
+    let (na, da) = tf_values(env, lhs).ok_or_else(error)?;
+    if let Token::Op(TokenOp::Caret) = tok {
+        let exp = match rhs {
+            Node::Num(n, _) => *n,
+            _ => return Err(error()),
+        };
+        let degree = (na.len().max(da.len()) - 1) as u128;
+        if degree.saturating_mul(exp.unsigned_abs()) > TF_DEGREE_MAX as u128 {
+            return Err(MyError::EvalError(format!(
+                "transfer function: degree should be {} or less",
+                TF_DEGREE_MAX
+            )));
+        }
+        let (base_num, base_den) = if exp < 0 { (da, na) } else { (na, da) };
+        let num = poly_pow(&base_num, exp.unsigned_abs());
+        let den = poly_pow(&base_den, exp.unsigned_abs());
+        return tf_result(&num, &den);
+    }
+    let (nb, db) = tf_values(env, rhs).ok_or_else(error)?;
+    let (num, den) = match tok {
+        Token::Op(TokenOp::Mul) => (poly_mul(&na, &nb), poly_mul(&da, &db)),
+        Token::Op(TokenOp::Div) => (poly_mul(&na, &db), poly_mul(&da, &nb)),
+        Token::Op(TokenOp::Plus) | Token::Op(TokenOp::Minus) => {
+            let sign = if *tok == Token::Op(TokenOp::Plus) {
+                1.0
+            } else {
+                -1.0
+            };
+            if da == db {
+                (poly_add(&na, &nb, sign), da)
+            } else {
+                (
+                    poly_add(&poly_mul(&na, &db), &poly_mul(&nb, &da), sign),
+                    poly_mul(&da, &db),
+                )
+            }
+        }
+        _ => return Err(error()),
+    };
+    tf_result(&num, &den)
+}
+
+// 0.001*s^2 - s + 1
+fn poly_string(env: &mut Env, p: &[Node]) -> String {
+    let mut ret = String::new();
+    let degree = p.len() - 1;
+    for (i, c) in p.iter().enumerate() {
+        let power = degree - i;
+        let (negative, c) = match c {
+            Node::Num(n, u) if *n < 0 => (true, Node::Num(-n, u.clone())),
+            Node::FNum(f, u) if *f < 0.0 => (true, Node::FNum(-f, u.clone())),
+            c => (false, c.clone()),
+        };
+        let is_zero = matches!(c, Node::Num(0, _)) || matches!(c, Node::FNum(f, _) if f == 0.0);
+        if is_zero && (power > 0 || !ret.is_empty()) {
+            continue;
+        }
+        let is_one = matches!(c, Node::Num(1, _)) || matches!(c, Node::FNum(f, _) if f == 1.0);
+        let coef = match c {
+            Node::CNum(_, _) => format!("({})", output_format(env, &c)),
+            _ => output_format(env, &c),
+        };
+        let term = match (power, is_one) {
+            (0, _) => coef,
+            (1, true) => "s".to_owned(),
+            (1, false) => format!("{}*s", coef),
+            (_, true) => format!("s^{}", power),
+            (_, false) => format!("{}*s^{}", coef, power),
+        };
+        if ret.is_empty() {
+            ret = if negative { format!("-{}", term) } else { term };
+        } else {
+            ret.push_str(if negative { " - " } else { " + " });
+            ret.push_str(&term);
+        }
+    }
+    ret
+}
+
+/// Transfer function as `(num)/(den)` of `s`, i.e. `1/(0.001*s + 1)`.
+pub fn output_format_tf(env: &mut Env, num: &[Node], den: &[Node]) -> String {
+    let num_string = poly_string(env, num);
+    let den_string = poly_string(env, den);
+    // a/2*s is (a/2)*s
+    let num_paren = num_string.contains(' ');
+    let den_paren =
+        den_string.contains(' ') || den_string.contains('*') || den_string.starts_with('-');
+    let paren = |s: String, p: bool| if p { format!("({})", s) } else { s };
+    format!(
+        "{}/{}",
+        paren(num_string, num_paren),
+        paren(den_string, den_paren)
+    )
+}
+
+// Gain, zeros and poles of a transfer function, for the continuous phase.
+struct TfRoots {
+    num: Poly,
+    den: Poly,
+    gain: Complex64,
+    zeros: Poly,
+    poles: Poly,
+}
+
+fn tf_roots(env: &Env, n: &Node) -> Option<TfRoots> {
+    let (num, den) = match n {
+        Node::TransferFn(_, _) => tf_values(env, n)?,
+        _ => return None,
+    };
+    // the zero numerator has no zeros
+    let zeros = if num == [Complex64::new(0.0, 0.0)] {
+        Vec::new()
+    } else {
+        poly_roots(&num)?
+    };
+    Some(TfRoots {
+        gain: num[0] / den[0],
+        zeros,
+        poles: poly_roots(&den)?,
+        num,
+        den,
+    })
+}
+
+impl TfRoots {
+    // H(jω)
+    fn response(&self, omega: f64) -> Complex64 {
+        let s = Complex64::new(0.0, omega);
+        poly_value(&self.num, s).0 / poly_value(&self.den, s).0
+    }
+
+    fn magnitude_db(&self, omega: f64) -> f64 {
+        20.0 * self.response(omega).norm().log10()
+    }
+
+    // Phase in radian, continuous over ω > 0 as the sum of phases of zeros and poles.
+    fn phase(&self, omega: f64) -> f64 {
+        let s = Complex64::new(0.0, omega);
+        let zeros: f64 = self.zeros.iter().map(|z| (s - z).arg()).sum();
+        let poles: f64 = self.poles.iter().map(|p| (s - p).arg()).sum();
+        self.gain.arg() + zeros - poles
+    }
+
+    // Angular frequencies of the scan, decades around zeros and poles.
+    fn omega_range(&self) -> (f64, f64) {
+        let norms: Vec<f64> = self
+            .zeros
+            .iter()
+            .chain(self.poles.iter())
+            .map(|r| r.norm())
+            .filter(|r| *r > 0.0)
+            .collect();
+        if norms.is_empty() {
+            return (1e-3, 1e3);
+        }
+        let min = norms.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = norms.iter().cloned().fold(0.0, f64::max);
+        (min * 1e-4, max * 1e4)
+    }
+
+    // Lowest angular frequency where `f` changes its sign from the value at the low end.
+    // The bracket of the log-spaced scan is narrowed by bisection.
+    fn first_crossing<F>(&self, f: F) -> Option<f64>
+    where
+        F: Fn(f64) -> f64,
+    {
+        let (lo, hi) = self.omega_range();
+        let steps = (100.0 * (hi / lo).log10()).ceil() as usize;
+        let ratio = (hi / lo).powf(1.0 / steps as f64);
+        let mut a = lo;
+        let fa = f(a);
+        for _ in 0..steps {
+            let b = a * ratio;
+            if f(b).signum() != fa.signum() {
+                let (mut a, mut b) = (a, b);
+                for _ in 0..100 {
+                    let center = (a * b).sqrt();
+                    if f(center).signum() == fa.signum() {
+                        a = center;
+                    } else {
+                        b = center;
+                    }
+                }
+                return Some((a * b).sqrt());
+            }
+            a = b;
+        }
+        None
+    }
+}
+
+fn frequency_arg(env: &Env, n: &Node) -> Option<f64> {
+    match n {
+        Node::Num(_, _) | Node::FNum(_, _) => eval_fvalue(env, n).ok(),
+        _ => None,
+    }
+}
+
+fn real_node(f: f64) -> Node {
+    Node::FNum(f, Box::new(Node::Units(Box::new(Node::None))))
+}
+
+/// tf(num, den): transfer function num(s)/den(s), coefficients are lists from the highest degree.
+/// i.e. tf({1}, {1m, 1}) = 1/(0.001*s + 1).
+pub fn impl_tf(env: &mut Env, arg: &[Node]) -> Node {
+    let coefficients = |n: &Node| match n {
+        Node::List(items) => items
+            .iter()
+            .map(|c| coefficient(env, c))
+            .collect::<Option<Poly>>(),
+        _ => coefficient(env, n).map(|c| vec![c]),
+    };
+    match arg {
+        [num, den] => match (coefficients(num), coefficients(den)) {
+            (Some(num), Some(den)) => {
+                let (num, den) = (poly_trim(&num), poly_trim(&den));
+                if den == [Complex64::new(0.0, 0.0)] {
+                    Node::None
+                } else {
+                    tf_node(&num, &den)
+                }
+            }
+            _ => Node::None,
+        },
+        _ => Node::None,
+    }
+}
+
+/// freqresp(H, f): complex response H(j 2π f) at the frequency `f` [Hz].
+pub fn impl_freqresp(env: &mut Env, arg: &[Node]) -> Node {
+    match (tf_roots(env, &arg[0]), frequency_arg(env, &arg[1])) {
+        (Some(tf), Some(f)) => tf_scalar(tf.response(2.0 * PI * f)),
+        _ => Node::None,
+    }
+}
+
+/// bodemag(H, f): gain [dB] at the frequency `f` [Hz].
+pub fn impl_bodemag(env: &mut Env, arg: &[Node]) -> Node {
+    match (tf_roots(env, &arg[0]), frequency_arg(env, &arg[1])) {
+        (Some(tf), Some(f)) => real_node(tf.magnitude_db(2.0 * PI * f)),
+        _ => Node::None,
+    }
+}
+
+/// bodephase(H, f): phase at the frequency `f` [Hz] in the angle mode.
+/// The phase is continuous, i.e. -270 deg for the third order low-pass filter.
+pub fn impl_bodephase(env: &mut Env, arg: &[Node]) -> Node {
+    match (tf_roots(env, &arg[0]), frequency_arg(env, &arg[1])) {
+        (Some(tf), Some(f)) => real_node(tf.phase(2.0 * PI * f) / env.angle_mode.radian()),
+        _ => Node::None,
+    }
+}
+
+/// bandwidth(H): frequency [Hz] where the gain falls 3 dB below the DC gain.
+pub fn impl_bandwidth(env: &mut Env, arg: &[Node]) -> Node {
+    let tf = match tf_roots(env, &arg[0]) {
+        Some(tf) => tf,
+        None => return Node::None,
+    };
+    let dc = tf.num[tf.num.len() - 1] / tf.den[tf.den.len() - 1];
+    if dc.norm() == 0.0 || !dc.norm().is_finite() {
+        return Node::None;
+    }
+    let level = 20.0 * dc.norm().log10() + 20.0 * std::f64::consts::FRAC_1_SQRT_2.log10();
+    match tf.first_crossing(|omega| tf.magnitude_db(omega) - level) {
+        Some(omega) => real_node(omega / (2.0 * PI)),
+        None => Node::None,
+    }
+}
+
+/// gm(H): {gain margin [dB], phase crossover frequency [Hz]} of the loop gain `H`.
+/// {inf, inf} without phase crossover.
+pub fn impl_gm(env: &mut Env, arg: &[Node]) -> Node {
+    let tf = match tf_roots(env, &arg[0]) {
+        Some(tf) => tf,
+        None => return Node::None,
+    };
+    // The continuous phase crosses -180 deg (+ 360 deg * n). The odd multiples of 180 deg
+    // around the phase at the low end are `lower` and `upper`, the product changes its sign
+    // only when the phase crosses one of them.
+    let (lo, _) = tf.omega_range();
+    let turns = ((tf.phase(lo) + PI) / (2.0 * PI)).floor();
+    let (lower, upper) = ((2.0 * turns - 1.0) * PI, (2.0 * turns + 1.0) * PI);
+    let crossing = tf.first_crossing(|omega| {
+        let phase = tf.phase(omega);
+        (phase - lower) * (phase - upper)
+    });
+    match crossing {
+        Some(omega) => Node::List(vec![
+            real_node(-tf.magnitude_db(omega)),
+            real_node(omega / (2.0 * PI)),
+        ]),
+        None => Node::List(vec![real_node(f64::INFINITY), real_node(f64::INFINITY)]),
+    }
+}
+
+/// pm(H): {phase margin, gain crossover frequency [Hz]} of the loop gain `H`.
+/// The phase margin is in the angle mode, {inf, inf} without gain crossover.
+pub fn impl_pm(env: &mut Env, arg: &[Node]) -> Node {
+    let tf = match tf_roots(env, &arg[0]) {
+        Some(tf) => tf,
+        None => return Node::None,
+    };
+    match tf.first_crossing(|omega| tf.magnitude_db(omega)) {
+        Some(omega) => {
+            // wrap to (-180, 180] deg
+            let margin = PI - (PI - (PI + tf.phase(omega))).rem_euclid(2.0 * PI);
+            Node::List(vec![
+                real_node(margin / env.angle_mode.radian()),
+                real_node(omega / (2.0 * PI)),
+            ])
+        }
+        None => Node::List(vec![real_node(f64::INFINITY), real_node(f64::INFINITY)]),
+    }
+}
+
+/// poles(H): roots of the denominator.
+pub fn impl_poles(env: &mut Env, arg: &[Node]) -> Node {
+    match tf_roots(env, &arg[0]) {
+        Some(tf) => Node::List(tf.poles.iter().map(|c| tf_scalar(*c)).collect()),
+        None => Node::None,
+    }
+}
+
+/// zeros(H): roots of the numerator.
+pub fn impl_zeros(env: &mut Env, arg: &[Node]) -> Node {
+    match tf_roots(env, &arg[0]) {
+        Some(tf) => Node::List(tf.zeros.iter().map(|c| tf_scalar(*c)).collect()),
+        None => Node::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(re: f64) -> Complex64 {
+        Complex64::new(re, 0.0)
+    }
+
+    fn tf(env: &Env, num: &[f64], den: &[f64]) -> TfRoots {
+        let num: Poly = num.iter().map(|x| c(*x)).collect();
+        let den: Poly = den.iter().map(|x| c(*x)).collect();
+        tf_roots(env, &tf_node(&num, &den)).unwrap()
+    }
+
+    #[test]
+    fn test_poly() {
+        assert_eq!(
+            poly_mul(&[c(1.0), c(1.0)], &[c(1.0), c(-1.0)]),
+            vec![c(1.0), c(0.0), c(-1.0)]
+        );
+        assert_eq!(
+            poly_add(&[c(1.0), c(2.0)], &[c(3.0)], 1.0),
+            vec![c(1.0), c(5.0)]
+        );
+        assert_eq!(
+            poly_add(&[c(1.0), c(2.0)], &[c(1.0), c(2.0)], -1.0),
+            vec![c(0.0)]
+        );
+    }
+
+    #[test]
+    fn test_tf_response() {
+        let env = Env::new();
+        // first order low-pass, pole at 1000 rad/s
+        let lpf = tf(&env, &[1.0], &[1e-3, 1.0]);
+        assert!((lpf.magnitude_db(1000.0) + 10.0 * 2f64.log10()).abs() < 1e-12);
+        assert!((lpf.phase(1000.0) + PI / 4.0).abs() < 1e-12);
+        // third order, continuous phase
+        let lpf3 = tf(&env, &[1.0], &[1.0, 3.0, 3.0, 1.0]);
+        assert!((lpf3.phase(1e6) + 1.5 * PI).abs() < 1e-5);
+        // integrator and unstable pole
+        assert!((tf(&env, &[1.0], &[1.0, 0.0]).phase(1.0) + PI / 2.0).abs() < 1e-12);
+        assert!((tf(&env, &[1.0], &[1.0, -1.0]).phase(1e-9).abs() - PI).abs() < 1e-6);
+
+        let omega = lpf
+            .first_crossing(|omega| lpf.magnitude_db(omega) + 20.0 * 2f64.sqrt().log10())
+            .unwrap();
+        assert!((omega - 1000.0).abs() < 1e-9);
+    }
+}
@@ -3,10 +3,59 @@ use std::cmp::Ordering;
 use std::collections::HashMap;
 
 fn units_unpack(units: Node) -> Node {
-    if let Node::Units(u) = units {
-        return *u;
+    match units {
+        Node::Units(u) => match *u {
+            Node::UnitsFraction(numerator, denominator) => {
+                match units_fraction_to_expr(&numerator, &denominator) {
+                    Node::None => Node::UnitsFraction(numerator, denominator),
+                    expr => expr,
+                }
+            }
+            u => u,
+        },
+        _ => units,
+    }
+}
+
+// m^2 s => m*m*s, "_" (no units) is skipped.
+fn units_hash_to_expr(hash: &HashMap<String, i32>) -> Node {
+    let mut vec: Vec<(&String, &i32)> = hash.iter().collect();
+    vec.sort_by(|a, b| a.0.cmp(b.0));
+    let mut node = Node::None;
+    for (key, count) in vec {
+        if key == "_" {
+            continue;
+        }
+        for _ in 0..*count {
+            let var = Node::Var(Token::Ident(key.clone()));
+            node = match node {
+                Node::None => var,
+                _ => Node::BinOp(Token::Op(TokenOp::Mul), Box::new(node), Box::new(var)),
+            };
+        }
+    }
+    node
+}
+
+// Evaluated units back to expression, so that they can be combined with units of literal,
+// i.e. abs(1[m])/2[s].
+fn units_fraction_to_expr(
+    numerator: &HashMap<String, i32>,
+    denominator: &HashMap<String, i32>,
+) -> Node {
+    match (
+        units_hash_to_expr(numerator),
+        units_hash_to_expr(denominator),
+    ) {
+        (Node::None, Node::None) => Node::None,
+        (nume, Node::None) => nume,
+        (Node::None, denom) => Node::BinOp(
+            Token::Op(TokenOp::Div),
+            Box::new(Node::Num(1, Box::new(Node::Units(Box::new(Node::None))))),
+            Box::new(denom),
+        ),
+        (nume, denom) => Node::BinOp(Token::Op(TokenOp::Div), Box::new(nume), Box::new(denom)),
     }
-    units
 }
 
 pub fn eval_units_mul(env: &mut Env, lhs_u: &Node, rhs_u: &Node) -> Node {
@@ -35,6 +84,9 @@ pub fn eval_units_div(env: &mut Env, lhs_u: &Node, rhs_u: &Node) -> Node {
     let lhs_uu = units_unpack(lhs_u.clone());
     let rhs_uu = units_unpack(rhs_u.clone());
 
+    if let (Node::None, false) = (&rhs_uu, matches!(lhs_uu, Node::None)) {
+        return lhs_uu; // (rhs_u == None) ==> return lhs_u
+    }
     match (lhs_u, rhs_u) {
         (Node::None, Node::None) => Node::Units(Box::new(Node::None)),
         (Node::None, _) => Node::Units(Box::new(Node::BinOp(
@@ -314,6 +366,29 @@ pub fn eval_units_fraction(env: &mut Env, units: Node) -> Node {
     }
 }
 
+// Numerator and denominator of the units, "_" (no units) is removed.
+fn units_dimension(
+    env: &mut Env,
+    units: &Node,
+) -> Option<(HashMap<String, i32>, HashMap<String, i32>)> {
+    match eval_units_fraction(env, units_unpack(units.clone())) {
+        Node::UnitsFraction(mut numerator, mut denominator) => {
+            numerator.remove("_");
+            denominator.remove("_");
+            Some((numerator, denominator))
+        }
+        _ => None,
+    }
+}
+
+// Units are the same after reduced to fractions, i.e. [m*s/s] and [m].
+pub fn units_equal(env: &mut Env, lhs: &Node, rhs: &Node) -> bool {
+    match (units_dimension(env, lhs), units_dimension(env, rhs)) {
+        (Some(lhs), Some(rhs)) => lhs == rhs,
+        _ => units_unpack(lhs.clone()) == units_unpack(rhs.clone()),
+    }
+}
+
 pub fn eval_unit(env: &mut Env, units: &Node) -> (Node, bool) {
     if env.is_debug() {
         eprintln!("eval_unit_prefix {:?}\r", units);
@@ -398,6 +473,28 @@ pub fn eval_unit(env: &mut Env, units: &Node) -> (Node, bool) {
                 ),
                 false,
             ),
+            // angle is converted into the current angle mode
+            "rad" => (
+                Node::FNum(
+                    AngleMode::Rad.radian() / env.angle_mode.radian(),
+                    Box::new(Node::Units(Box::new(Node::None))),
+                ),
+                false,
+            ),
+            "deg" => (
+                Node::FNum(
+                    AngleMode::Deg.radian() / env.angle_mode.radian(),
+                    Box::new(Node::Units(Box::new(Node::None))),
+                ),
+                false,
+            ),
+            "grad" => (
+                Node::FNum(
+                    AngleMode::Grad.radian() / env.angle_mode.radian(),
+                    Box::new(Node::Units(Box::new(Node::None))),
+                ),
+                false,
+            ),
             _ => (Node::Num(1, Box::new(units.clone())), true),
         },
         Node::BinOp(op, lhs, rhs) => {
@@ -576,6 +673,17 @@ mod tests {
         assert_eq!(unpacked, node);
     }
 
+    #[test]
+    fn test_units_div_dimensionless() {
+        let mut env = Env::new();
+        env.built_in();
+
+        // abs(1)/2: the evaluated units of abs(1) is an empty fraction
+        let lhs = Node::UnitsFraction(HashMap::new(), HashMap::new());
+        let rhs = Node::Units(Box::new(Node::None));
+        assert_eq!(eval_units_div(&mut env, &lhs, &rhs), lhs);
+    }
+
     #[test]
     fn test_units_fraction_reduce() {
         let mut env = Env::new();
@@ -74,6 +74,37 @@ fn runs7() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn runs_units() -> TestResult {
+    Command::cargo_bin("rc")?
+        .arg("6[m]/2[s]")
+        .assert()
+        .success()
+        .stdout("3 [m/s]\n");
+    Ok(())
+}
+
+#[test]
+fn runs_dimensionless_div() -> TestResult {
+    Command::cargo_bin("rc")?
+        .arg("abs(1)/2")
+        .assert()
+        .success()
+        .stdout("0.5\n");
+    Ok(())
+}
+
+#[test]
+fn runs_format_script() -> TestResult {
+    Command::cargo_bin("rc")?
+        .args(["-s", "-"])
+        .write_stdin("format eng digits 3\n1/(2*pi*10k*4.7n)\n1k[m]*2.2u[s]\nexit\n")
+        .assert()
+        .success()
+        .stdout("\n3.39k\n2.20m [m s]\n");
+    Ok(())
+}
+
 fn normalize_newlines(input: String) -> String {
     if cfg!(target_os = "windows") {
         input.replace("\r\n", "\n")